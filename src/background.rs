@@ -0,0 +1,122 @@
+use crate::{spaces, Color, Framebuffer, Vector};
+use std::f64::consts::PI;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+/// The color seen by rays which hit no object in the world.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Background {
+    /// The same color in every direction
+    Solid(Color),
+
+    /// A sky fading from `horizon`, at and below the horizon, to `zenith`, directly overhead
+    Gradient { horizon: Color, zenith: Color },
+
+    /// An equirectangular ("latitude-longitude") image surrounding the world.  The top and
+    /// bottom rows of the image are along the +y and -y axes, and the center of the image is
+    /// along the -z axis.  The image must not be empty; see `Background::image`.
+    Image(Arc<Framebuffer>),
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Solid(Color::black())
+    }
+}
+
+impl Background {
+    /// Surround the world with an equirectangular image.  Panics if the image is empty.
+    pub fn image(img: Arc<Framebuffer>) -> Self {
+        assert!(!img.is_empty(), "background image is empty");
+        Background::Image(img)
+    }
+
+    /// Load an equirectangular image from the given file.  See `Framebuffer::load`.
+    pub fn load_image<P: AsRef<Path>>(path: P) -> Result<Self, image::ImageError> {
+        let img = Framebuffer::load(path)?;
+        if img.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "image is empty").into());
+        }
+        Ok(Background::image(Arc::new(img)))
+    }
+
+    /// Get the color seen looking in the given direction.
+    pub fn color_at(&self, direction: Vector<spaces::World>) -> Color {
+        match self {
+            Background::Solid(color) => *color,
+            Background::Gradient { horizon, zenith } => {
+                let t = direction.normalize().y.max(0.0);
+                *horizon * (1.0 - t) + *zenith * t
+            }
+            Background::Image(img) => {
+                let d = direction.normalize();
+                let u = 0.5 + d.x.atan2(-d.z) / (2.0 * PI);
+                let v = d.y.clamp(-1.0, 1.0).acos() / PI;
+                let x = ((u * img.width() as f64) as u32).min(img.width() - 1);
+                let y = ((v * img.height() as f64) as u32).min(img.height() - 1);
+                img.get(x, y)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::*;
+    use image::{Rgb, Rgb32FImage};
+
+    #[test]
+    fn solid() {
+        let bg = Background::Solid(Color::new(0.1, 0.2, 0.3));
+        assert_relative_eq!(bg.color_at(Vector::new(1, 2, 3)), Color::new(0.1, 0.2, 0.3));
+    }
+
+    #[test]
+    fn gradient() {
+        let bg = Background::Gradient {
+            horizon: Color::white(),
+            zenith: Color::new(0, 0, 1),
+        };
+        assert_relative_eq!(bg.color_at(Vector::new(0, 5, 0)), Color::new(0, 0, 1));
+        assert_relative_eq!(bg.color_at(Vector::new(1, 0, 0)), Color::white());
+        assert_relative_eq!(bg.color_at(Vector::new(0, -1, 1)), Color::white());
+        assert_relative_eq!(
+            bg.color_at(Vector::new(0, 1, 3f64.sqrt())),
+            Color::new(0.5, 0.5, 1)
+        );
+    }
+
+    #[test]
+    fn image() {
+        // each pixel's red and green components are its coordinates
+        let img = Rgb32FImage::from_fn(4, 2, |x, y| Rgb([x as f32, y as f32, 0.0]));
+        let bg = Background::image(Arc::new(Framebuffer::from(&img)));
+        for (direction, x, y) in [
+            (Vector::new(0, 0.1, -1), 2, 0),
+            (Vector::new(0, -0.1, -1), 2, 1),
+            (Vector::new(1, 0.1, 0), 3, 0),
+            (Vector::new(-1, 0.1, 0), 1, 0),
+            (Vector::new(-0.1, 0.1, 1), 0, 0),
+            (Vector::new(0, -1, -0.01), 2, 1),
+        ] {
+            assert_relative_eq!(bg.color_at(direction), Color::new(x, y, 0));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "background image is empty")]
+    fn empty_image() {
+        Background::image(Arc::new(Framebuffer::new(0, 4)));
+    }
+
+    #[test]
+    fn load_empty_image() {
+        let path = std::env::temp_dir().join(format!("rtc-empty-{}.ppm", std::process::id()));
+        std::fs::write(&path, "P3 0 0 255\n").unwrap();
+        let err = Background::load_image(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(err.to_string(), "image is empty");
+    }
+}
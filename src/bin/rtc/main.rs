@@ -0,0 +1,356 @@
+use ray_tracer_challenge::*;
+use std::process::exit;
+use std::time::Instant;
+
+const USAGE: &str = "\
+usage: rtc [options] <scene.yaml> <output.png>
+
+Render a scene file to an image.  If the output file name ends in .hdr, the image is
+saved losslessly in Radiance HDR format; if it ends in .ppm, the image is saved as
+plain-text PPM, or binary PPM with --binary.
+
+options:
+    --width <pixels>      override the width of the image
+    --height <pixels>     override the height of the image
+    --oversample <n>      cast n x n rays through each pixel
+    --integrator <name>   render with whitted (default) ray tracing, or path tracing
+    --samples <n>         trace n paths for each ray when path tracing (default: 1)
+    --seed <n>            seed the random numbers used by path tracing (default: 0)
+    --threads <n>         render with n threads (default: one per CPU)
+    --tone-map <op>       map colors for display with clamp (default), reinhard or aces
+    --exposure <stops>    brighten (or, if negative, darken) the image before tone mapping
+    --srgb                apply sRGB gamma encoding after tone mapping
+    --binary              save .ppm output in binary (P6) rather than plain (P3) format
+    --help                show this message
+
+Tone mapping does not apply to .hdr output.";
+
+/// Options given on the command line.
+#[derive(Debug, Default)]
+struct Args {
+    scene: String,
+    output: String,
+    width: Option<u32>,
+    height: Option<u32>,
+    oversample: Option<u32>,
+    threads: Option<usize>,
+    tone_map: ToneMap,
+    integrator: Integrator,
+    samples: Option<u32>,
+    seed: u64,
+    ppm_format: Option<PpmFormat>,
+}
+
+/// The ways of calculating the color seen along each ray.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Integrator {
+    /// Recursive ray tracing with the Phong model, in `World`
+    #[default]
+    Whitted,
+
+    /// Monte Carlo path tracing, with `PathTracer`
+    Path,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+        let mut result = Args::default();
+        let mut positional = vec![];
+        let mut operator = ToneMapOperator::default();
+        let mut exposure = 0.0;
+        let mut srgb = false;
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                positional.push(arg);
+                continue;
+            }
+            if arg == "--help" {
+                println!("{}", USAGE);
+                exit(0);
+            }
+            if arg == "--srgb" {
+                srgb = true;
+                continue;
+            }
+            if arg == "--binary" {
+                result.ppm_format = Some(PpmFormat::Binary);
+                continue;
+            }
+            let value = args
+                .next()
+                .ok_or_else(|| format!("missing value for {}", arg))?;
+            match arg.as_str() {
+                "--width" => result.width = Some(positive(&arg, &value)?),
+                "--height" => result.height = Some(positive(&arg, &value)?),
+                "--oversample" => result.oversample = Some(positive(&arg, &value)?),
+                "--threads" => result.threads = Some(positive(&arg, &value)?),
+                "--integrator" => {
+                    result.integrator = match value.as_str() {
+                        "whitted" => Integrator::Whitted,
+                        "path" => Integrator::Path,
+                        _ => return Err(format!("unknown integrator {:?}", value)),
+                    }
+                }
+                "--samples" => result.samples = Some(positive(&arg, &value)?),
+                "--seed" => {
+                    result.seed = value
+                        .parse()
+                        .map_err(|_| format!("{} must be an integer, not {:?}", arg, value))?
+                }
+                "--tone-map" => operator = value.parse().map_err(|e| format!("{}", e))?,
+                "--exposure" => {
+                    exposure = value
+                        .parse()
+                        .map_err(|_| format!("{} must be a number, not {:?}", arg, value))?
+                }
+                _ => return Err(format!("unknown option {}", arg)),
+            }
+        }
+
+        if result.samples.is_some() && result.integrator != Integrator::Path {
+            return Err("--samples requires --integrator path".to_string());
+        }
+
+        result.tone_map = ToneMap::new(operator)
+            .with_exposure(exposure)
+            .with_srgb(srgb);
+
+        let Ok([scene, output]) = <[String; 2]>::try_from(positional) else {
+            return Err("expected a scene file and an output file".to_string());
+        };
+        if result.ppm_format.is_some() && !output.to_ascii_lowercase().ends_with(".ppm") {
+            return Err("--binary requires a .ppm output file".to_string());
+        }
+        result.scene = scene;
+        result.output = output;
+        Ok(result)
+    }
+}
+
+/// Parse a positive integer value for the given option.
+fn positive<T: std::str::FromStr + Default + PartialOrd>(
+    option: &str,
+    value: &str,
+) -> Result<T, String> {
+    match value.parse() {
+        Ok(n) if n > T::default() => Ok(n),
+        _ => Err(format!(
+            "{} must be a positive integer, not {:?}",
+            option, value
+        )),
+    }
+}
+
+fn main() {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("rtc: {}\n\n{}", msg, USAGE);
+            exit(2);
+        }
+    };
+
+    if let Err(msg) = render(&args) {
+        eprintln!("rtc: {}", msg);
+        exit(1);
+    }
+}
+
+fn render(args: &Args) -> Result<(), String> {
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .map_err(|e| format!("could not start threads: {}", e))?;
+    }
+
+    let start = Instant::now();
+    let Scene { world, mut camera } =
+        load_scene(&args.scene).map_err(|e| format!("{}: {}", args.scene, e))?;
+    // when only one dimension is given, keep the scene's aspect ratio
+    let aspect = camera.hsize() as f64 / camera.vsize() as f64;
+    let size = match (args.width, args.height) {
+        (Some(w), Some(h)) => Some((w, h)),
+        (Some(w), None) => Some((w, ((w as f64 / aspect).round() as u32).max(1))),
+        (None, Some(h)) => Some((((h as f64 * aspect).round() as u32).max(1), h)),
+        (None, None) => None,
+    };
+    if let Some((width, height)) = size {
+        camera = camera.with_size(width, height);
+    }
+    if let Some(oversample) = args.oversample {
+        camera = camera.with_oversample(oversample);
+    }
+    let loaded = Instant::now();
+
+    let fb = match args.integrator {
+        Integrator::Whitted => camera.render(&world),
+        Integrator::Path => camera.render(
+            &PathTracer::new(&world)
+                .with_seed(args.seed)
+                .with_samples(args.samples.unwrap_or(1)),
+        ),
+    };
+    let rendered = Instant::now();
+
+    let output = args.output.to_ascii_lowercase();
+    let saved = if output.ends_with(".hdr") {
+        fb.save_hdr(&args.output).map_err(|e| e.to_string())
+    } else if output.ends_with(".ppm") {
+        let format = args.ppm_format.unwrap_or(PpmFormat::Plain);
+        fb.save_ppm(&args.output, args.tone_map, format)
+            .map_err(|e| e.to_string())
+    } else {
+        fb.to_image(args.tone_map)
+            .save(&args.output)
+            .map_err(|e| e.to_string())
+    };
+    saved.map_err(|e| format!("{}: {}", args.output, e))?;
+
+    let load_time = (loaded - start).as_secs_f64();
+    let render_time = (rendered - loaded).as_secs_f64();
+    let stats = world.ray_stats();
+    // when path tracing, each ray from the camera starts several paths
+    let paths = match args.integrator {
+        Integrator::Whitted => 1,
+        Integrator::Path => args.samples.unwrap_or(1),
+    };
+    let primary = camera.hsize() as u64
+        * camera.vsize() as u64
+        * camera.rays_per_pixel() as u64
+        * paths as u64;
+    let total = stats.rays + stats.shadow_rays;
+    println!(
+        "rendered {}x{} in {:.2}s (loading took {:.2}s)",
+        camera.hsize(),
+        camera.vsize(),
+        render_time,
+        load_time
+    );
+    println!(
+        "{} rays: {} primary, {} secondary, {} shadow ({:.0} rays/s)",
+        total,
+        primary,
+        stats.rays.saturating_sub(primary),
+        stats.shadow_rays,
+        // a tiny scene may render faster than the timer's resolution
+        total as f64 / render_time.max(1e-6)
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Args, String> {
+        Args::parse(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn defaults() {
+        let args = parse("scene.yaml out.png").unwrap();
+        assert_eq!(args.scene, "scene.yaml");
+        assert_eq!(args.output, "out.png");
+        assert_eq!(
+            (args.width, args.height, args.oversample),
+            (None, None, None)
+        );
+        assert_eq!(args.threads, None);
+        assert_eq!(args.integrator, Integrator::Whitted);
+        assert_eq!(args.ppm_format, None);
+    }
+
+    #[test]
+    fn options() {
+        let args = parse(
+            "--width 640 --height 480 --oversample 2 --threads 4 --integrator path \
+             --samples 16 --seed 7 --binary scene.yaml out.ppm",
+        )
+        .unwrap();
+        assert_eq!((args.width, args.height), (Some(640), Some(480)));
+        assert_eq!(args.oversample, Some(2));
+        assert_eq!(args.threads, Some(4));
+        assert_eq!(args.integrator, Integrator::Path);
+        assert_eq!((args.samples, args.seed), (Some(16), 7));
+        assert_eq!(args.ppm_format, Some(PpmFormat::Binary));
+    }
+
+    #[test]
+    fn bad_resolution() {
+        for (args, message) in [
+            ("--width 0", "--width must be a positive integer, not \"0\""),
+            (
+                "--width -5",
+                "--width must be a positive integer, not \"-5\"",
+            ),
+            (
+                "--height 1.5",
+                "--height must be a positive integer, not \"1.5\"",
+            ),
+            (
+                "--height tall",
+                "--height must be a positive integer, not \"tall\"",
+            ),
+        ] {
+            assert_eq!(
+                parse(&format!("{} scene.yaml out.png", args)).unwrap_err(),
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn bad_oversample_and_threads() {
+        for (args, message) in [
+            (
+                "--oversample 0",
+                "--oversample must be a positive integer, not \"0\"",
+            ),
+            (
+                "--oversample x",
+                "--oversample must be a positive integer, not \"x\"",
+            ),
+            (
+                "--threads 0",
+                "--threads must be a positive integer, not \"0\"",
+            ),
+            (
+                "--threads -1",
+                "--threads must be a positive integer, not \"-1\"",
+            ),
+        ] {
+            assert_eq!(
+                parse(&format!("{} scene.yaml out.png", args)).unwrap_err(),
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn bad_usage() {
+        for (args, message) in [
+            ("scene.yaml out.png --width", "missing value for --width"),
+            ("--size 5 scene.yaml out.png", "unknown option --size"),
+            (
+                "--integrator photon scene.yaml out.png",
+                "unknown integrator \"photon\"",
+            ),
+            (
+                "--samples 4 scene.yaml out.png",
+                "--samples requires --integrator path",
+            ),
+            (
+                "--binary scene.yaml out.png",
+                "--binary requires a .ppm output file",
+            ),
+            ("scene.yaml", "expected a scene file and an output file"),
+            (
+                "a.yaml b.yaml out.png",
+                "expected a scene file and an output file",
+            ),
+        ] {
+            assert_eq!(parse(args).unwrap_err(), message);
+        }
+    }
+}
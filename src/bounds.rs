@@ -0,0 +1,215 @@
+use crate::{Mat, Point, Ray, Space};
+
+/// Bounds represents an axis-aligned bounding box in the given space.
+///
+/// Unbounded objects, such as planes, use infinite bounds.  Such bounds remain infinite when
+/// transformed into another space.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Bounds<S: Space> {
+    pub min: Point<S>,
+    pub max: Point<S>,
+}
+
+impl<S: Space> Bounds<S> {
+    /// Create new bounds with the given minimum and maximum corners.
+    pub fn new(min: Point<S>, max: Point<S>) -> Self {
+        Self { min, max }
+    }
+
+    /// Create empty bounds, containing no points at all.  This is the identity for `union`.
+    pub fn empty() -> Self {
+        Self {
+            min: Point::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            max: Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        }
+    }
+
+    /// Create infinite bounds, containing every point.
+    pub fn infinite() -> Self {
+        Self {
+            min: Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            max: Point::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+        }
+    }
+
+    /// Determine whether these bounds are finite (and not empty).
+    pub fn is_finite(&self) -> bool {
+        self.min.x.is_finite()
+            && self.min.y.is_finite()
+            && self.min.z.is_finite()
+            && self.max.x.is_finite()
+            && self.max.y.is_finite()
+            && self.max.z.is_finite()
+    }
+
+    /// Return the smallest bounds containing both these bounds and the given bounds.
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: Point::new(
+                self.min.x.min(other.min.x),
+                self.min.y.min(other.min.y),
+                self.min.z.min(other.min.z),
+            ),
+            max: Point::new(
+                self.max.x.max(other.max.x),
+                self.max.y.max(other.max.y),
+                self.max.z.max(other.max.z),
+            ),
+        }
+    }
+
+    /// Return the smallest bounds containing both these bounds and the given point.
+    pub fn with_point(&self, point: Point<S>) -> Self {
+        self.union(&Self::new(point, point))
+    }
+
+    /// Get the center of these bounds.
+    pub fn centroid(&self) -> Point<S> {
+        Point::new(
+            (self.min.x + self.max.x) / 2.0,
+            (self.min.y + self.max.y) / 2.0,
+            (self.min.z + self.max.z) / 2.0,
+        )
+    }
+
+    /// Get the surface area of these bounds, or zero if they are empty.
+    pub fn surface_area(&self) -> f64 {
+        let d = self.max - self.min;
+        if d.x < 0.0 || d.y < 0.0 || d.z < 0.0 {
+            return 0.0;
+        }
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    /// Transform these bounds into another space, returning the axis-aligned bounds of the
+    /// transformed box.
+    pub fn transform<S2: Space>(&self, xform: Mat<4, S, S2>) -> Bounds<S2> {
+        if !self.is_finite() {
+            return Bounds::infinite();
+        }
+
+        let mut res = Bounds::empty();
+        for x in [self.min.x, self.max.x] {
+            for y in [self.min.y, self.max.y] {
+                for z in [self.min.z, self.max.z] {
+                    res = res.with_point(xform * Point::new(x, y, z));
+                }
+            }
+        }
+        res
+    }
+
+    /// Determine whether the given ray passes through these bounds at any non-negative `t`.
+    ///
+    /// The `inv_direction` argument must contain the reciprocals of the ray direction's
+    /// components, which callers can calculate once for many bounds.
+    pub(crate) fn hit_by(&self, ray: &Ray<S>, inv_direction: (f64, f64, f64)) -> bool {
+        fn check_axis(min: f64, max: f64, origin: f64, inv_direction: f64) -> (f64, f64) {
+            let t1 = (min - origin) * inv_direction;
+            let t2 = (max - origin) * inv_direction;
+            if t1 < t2 {
+                (t1, t2)
+            } else {
+                (t2, t1)
+            }
+        }
+
+        let (xtmin, xtmax) = check_axis(self.min.x, self.max.x, ray.origin.x, inv_direction.0);
+        let (ytmin, ytmax) = check_axis(self.min.y, self.max.y, ray.origin.y, inv_direction.1);
+        let (ztmin, ztmax) = check_axis(self.min.z, self.max.z, ray.origin.z, inv_direction.2);
+
+        // NaN values (from 0 * infinity) are ignored by f64::max and f64::min
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+        tmin <= tmax && tmax >= 0.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{spaces, Vector};
+    use approx::*;
+
+    fn unit() -> Bounds<spaces::Object> {
+        Bounds::new(Point::new(-1, -1, -1), Point::new(1, 1, 1))
+    }
+
+    fn inv(ray: &Ray<spaces::Object>) -> (f64, f64, f64) {
+        (
+            1.0 / ray.direction.x,
+            1.0 / ray.direction.y,
+            1.0 / ray.direction.z,
+        )
+    }
+
+    #[test]
+    fn union() {
+        let a = Bounds::new(Point::new(-1, 0, 0), Point::new(1, 1, 1));
+        let b = Bounds::new(Point::new(0, -2, 0), Point::new(0.5, 0.5, 3));
+        let u: Bounds<spaces::Object> = a.union(&b);
+        assert_relative_eq!(u.min, Point::new(-1, -2, 0));
+        assert_relative_eq!(u.max, Point::new(1, 1, 3));
+    }
+
+    #[test]
+    fn union_empty() {
+        let u = Bounds::empty().union(&unit());
+        assert_eq!(u, unit());
+        assert_relative_eq!(Bounds::<spaces::Object>::empty().surface_area(), 0.0);
+    }
+
+    #[test]
+    fn surface_area() {
+        assert_relative_eq!(unit().surface_area(), 24.0);
+    }
+
+    #[test]
+    fn transform() {
+        let m: Mat<4, spaces::Object, spaces::World> = Mat::identity()
+            .rotate_z(std::f64::consts::PI / 4.0)
+            .translate(5, 0, 0);
+        let b = unit().transform(m);
+        let rt2 = 2f64.sqrt();
+        assert_relative_eq!(b.min, Point::new(5.0 - rt2, -rt2, -1.0));
+        assert_relative_eq!(b.max, Point::new(5.0 + rt2, rt2, 1.0));
+    }
+
+    #[test]
+    fn transform_infinite() {
+        let m: Mat<4, spaces::Object, spaces::World> = Mat::identity().rotate_x(1.0);
+        let b = Bounds::<spaces::Object>::infinite().transform(m);
+        assert!(!b.is_finite());
+        assert_eq!(b, Bounds::infinite());
+    }
+
+    #[test]
+    fn hit_by() {
+        let r = Ray::new(Point::new(-5, 0.5, 0), Vector::new(1, 0, 0));
+        assert!(unit().hit_by(&r, inv(&r)));
+    }
+
+    #[test]
+    fn hit_by_inside() {
+        let r = Ray::new(Point::new(0, 0, 0), Vector::new(0.3, 0.2, 0.1));
+        assert!(unit().hit_by(&r, inv(&r)));
+    }
+
+    #[test]
+    fn miss_behind() {
+        let r = Ray::new(Point::new(5, 0, 0), Vector::new(1, 0, 0));
+        assert!(!unit().hit_by(&r, inv(&r)));
+    }
+
+    #[test]
+    fn miss_oblique() {
+        let r = Ray::new(Point::new(-2, 0, 0), Vector::new(0.2673, 0.5345, 0.8018));
+        assert!(!unit().hit_by(&r, inv(&r)));
+    }
+
+    #[test]
+    fn hit_by_infinite() {
+        let r = Ray::new(Point::new(0, 10, 0), Vector::new(0, 0, 1));
+        assert!(Bounds::<spaces::Object>::infinite().hit_by(&r, inv(&r)));
+    }
+}
@@ -0,0 +1,347 @@
+use crate::{spaces, Bounds, Ray};
+
+/// Number of items at or below which a node is always a leaf
+const MIN_SPLIT_SIZE: usize = 4;
+
+/// Maximum number of items in a leaf node, even when splitting appears costly
+const MAX_LEAF_SIZE: usize = 16;
+
+/// Number of buckets used to estimate the surface area heuristic
+const SAH_BUCKETS: usize = 12;
+
+/// Maximum depth of the hierarchy, so that traversal can use a fixed-size stack.  Nodes at this
+/// depth are leaves, however many items they hold.
+const MAX_DEPTH: usize = 64;
+
+/// Relative cost of traversing an interior node, compared to intersecting one item
+const TRAVERSAL_COST: f64 = 0.125;
+
+/// A bounding volume hierarchy over a set of items with world-space bounds.
+///
+/// Items are identified by their index in the slice of bounds given to `Bvh::new`.  Items
+/// with infinite bounds (such as planes) are kept outside of the hierarchy and are always
+/// considered candidates for intersection.
+#[derive(Debug, Default)]
+pub(crate) struct Bvh {
+    /// Nodes of the hierarchy; the root, if any, is the first node.
+    nodes: Vec<BvhNode>,
+
+    /// Item indexes, ordered such that each leaf refers to a contiguous range.
+    items: Vec<usize>,
+
+    /// Items with infinite bounds.
+    unbounded: Vec<usize>,
+}
+
+#[derive(Debug)]
+enum BvhNode {
+    /// A leaf, containing `count` items beginning at `start` in `Bvh::items`
+    Leaf {
+        bounds: Bounds<spaces::World>,
+        start: usize,
+        count: usize,
+    },
+    /// An interior node, with the indexes of its two children in `Bvh::nodes`
+    Interior {
+        bounds: Bounds<spaces::World>,
+        left: usize,
+        right: usize,
+    },
+}
+
+impl BvhNode {
+    fn bounds(&self) -> &Bounds<spaces::World> {
+        match self {
+            BvhNode::Leaf { bounds, .. } => bounds,
+            BvhNode::Interior { bounds, .. } => bounds,
+        }
+    }
+}
+
+/// An item under consideration during construction.
+struct BuildItem {
+    index: usize,
+    bounds: Bounds<spaces::World>,
+    centroid: [f64; 3],
+}
+
+impl Bvh {
+    /// Build a new BVH over items with the given bounds, using the surface area heuristic to
+    /// choose how to split each node.
+    pub(crate) fn new(bounds: &[Bounds<spaces::World>]) -> Self {
+        let mut bvh = Self::default();
+        let mut build_items = vec![];
+        for (index, b) in bounds.iter().enumerate() {
+            if b.is_finite() {
+                let c = b.centroid();
+                build_items.push(BuildItem {
+                    index,
+                    bounds: *b,
+                    centroid: [c.x, c.y, c.z],
+                });
+            } else {
+                bvh.unbounded.push(index);
+            }
+        }
+
+        if !build_items.is_empty() {
+            bvh.build(&mut build_items[..], 0);
+        }
+        bvh
+    }
+
+    /// Build a subtree at the given depth containing the given items, returning the index of
+    /// its root node.
+    fn build(&mut self, items: &mut [BuildItem], depth: usize) -> usize {
+        let bounds = items
+            .iter()
+            .fold(Bounds::empty(), |acc, i| acc.union(&i.bounds));
+
+        if items.len() <= MIN_SPLIT_SIZE || depth == MAX_DEPTH {
+            return self.leaf(bounds, items);
+        }
+
+        // split along the axis with the largest extent of centroids
+        let (cmin, cmax) = items.iter().fold(
+            ([f64::INFINITY; 3], [f64::NEG_INFINITY; 3]),
+            |(mut cmin, mut cmax), i| {
+                for a in 0..3 {
+                    cmin[a] = cmin[a].min(i.centroid[a]);
+                    cmax[a] = cmax[a].max(i.centroid[a]);
+                }
+                (cmin, cmax)
+            },
+        );
+        let axis = (0..3)
+            .max_by(|&a, &b| {
+                (cmax[a] - cmin[a])
+                    .partial_cmp(&(cmax[b] - cmin[b]))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .unwrap();
+        let extent = cmax[axis] - cmin[axis];
+        if extent <= 0.0 {
+            // all centroids coincide, so there is no useful split
+            return self.leaf(bounds, items);
+        }
+
+        // assign items to buckets by centroid
+        let bucket_of = |i: &BuildItem| {
+            let b = ((i.centroid[axis] - cmin[axis]) / extent * SAH_BUCKETS as f64) as usize;
+            b.min(SAH_BUCKETS - 1)
+        };
+        let mut bucket_counts = [0usize; SAH_BUCKETS];
+        let mut bucket_bounds = [Bounds::empty(); SAH_BUCKETS];
+        for i in items.iter() {
+            let b = bucket_of(i);
+            bucket_counts[b] += 1;
+            bucket_bounds[b] = bucket_bounds[b].union(&i.bounds);
+        }
+
+        // find the split (after bucket `split`) with the lowest estimated cost
+        let mut best_split = 0;
+        let mut best_cost = f64::INFINITY;
+        for split in 0..SAH_BUCKETS - 1 {
+            let (mut lb, mut lc) = (Bounds::empty(), 0);
+            let (mut rb, mut rc) = (Bounds::empty(), 0);
+            for b in 0..=split {
+                lb = lb.union(&bucket_bounds[b]);
+                lc += bucket_counts[b];
+            }
+            for b in split + 1..SAH_BUCKETS {
+                rb = rb.union(&bucket_bounds[b]);
+                rc += bucket_counts[b];
+            }
+            let cost = TRAVERSAL_COST
+                + (lb.surface_area() * lc as f64 + rb.surface_area() * rc as f64)
+                    / bounds.surface_area();
+            if cost < best_cost {
+                best_cost = cost;
+                best_split = split;
+            }
+        }
+
+        // a leaf is cheaper than the best split
+        if best_cost >= items.len() as f64 && items.len() <= MAX_LEAF_SIZE {
+            return self.leaf(bounds, items);
+        }
+
+        // partition the items in-place
+        let mut mid = 0;
+        for i in 0..items.len() {
+            if bucket_of(&items[i]) <= best_split {
+                items.swap(i, mid);
+                mid += 1;
+            }
+        }
+        if mid == 0 || mid == items.len() {
+            return self.leaf(bounds, items);
+        }
+
+        // reserve this node's place, then build its children
+        let node = self.nodes.len();
+        self.nodes.push(BvhNode::Leaf {
+            bounds,
+            start: 0,
+            count: 0,
+        });
+        let (left_items, right_items) = items.split_at_mut(mid);
+        let left = self.build(left_items, depth + 1);
+        let right = self.build(right_items, depth + 1);
+        self.nodes[node] = BvhNode::Interior {
+            bounds,
+            left,
+            right,
+        };
+        node
+    }
+
+    fn leaf(&mut self, bounds: Bounds<spaces::World>, items: &[BuildItem]) -> usize {
+        let start = self.items.len();
+        self.items.extend(items.iter().map(|i| i.index));
+        self.nodes.push(BvhNode::Leaf {
+            bounds,
+            start,
+            count: items.len(),
+        });
+        self.nodes.len() - 1
+    }
+
+    /// Call `f` with the index of every item whose bounds the given ray might pass through
+    /// at a non-negative `t`.
+    pub(crate) fn intersect(&self, ray: &Ray<spaces::World>, mut f: impl FnMut(usize)) {
+        for &i in &self.unbounded {
+            f(i);
+        }
+
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let inv_direction = (
+            1.0 / ray.direction.x,
+            1.0 / ray.direction.y,
+            1.0 / ray.direction.z,
+        );
+        // each interior node replaces itself on the stack with its two children, so the stack
+        // holds at most one more node than the depth of the hierarchy
+        let mut stack = [0; MAX_DEPTH + 1];
+        let mut len = 1;
+        while len > 0 {
+            len -= 1;
+            let n = stack[len];
+            let node = &self.nodes[n];
+            if !node.bounds().hit_by(ray, inv_direction) {
+                continue;
+            }
+            match *node {
+                BvhNode::Leaf { start, count, .. } => {
+                    for &i in &self.items[start..start + count] {
+                        f(i);
+                    }
+                }
+                BvhNode::Interior { left, right, .. } => {
+                    stack[len] = right;
+                    stack[len + 1] = left;
+                    len += 2;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Point, Vector};
+
+    fn unit_at(x: f64, y: f64, z: f64) -> Bounds<spaces::World> {
+        Bounds::new(
+            Point::new(x - 0.5, y - 0.5, z - 0.5),
+            Point::new(x + 0.5, y + 0.5, z + 0.5),
+        )
+    }
+
+    fn candidates(bvh: &Bvh, ray: &Ray<spaces::World>) -> Vec<usize> {
+        let mut res = vec![];
+        bvh.intersect(ray, |i| res.push(i));
+        res.sort_unstable();
+        res
+    }
+
+    #[test]
+    fn empty() {
+        let bvh = Bvh::new(&[]);
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+        assert_eq!(candidates(&bvh, &r), vec![]);
+    }
+
+    #[test]
+    fn unbounded_always_candidates() {
+        let bvh = Bvh::new(&[Bounds::infinite(), unit_at(0.0, 0.0, 0.0)]);
+        let r = Ray::new(Point::new(0, 10, -5), Vector::new(0, 0, 1));
+        assert_eq!(candidates(&bvh, &r), vec![0]);
+    }
+
+    #[test]
+    fn row_of_boxes() {
+        // a 10x10 grid of boxes in x-y, two units apart
+        let mut bounds = vec![];
+        for x in 0..10 {
+            for y in 0..10 {
+                bounds.push(unit_at(x as f64 * 2.0, y as f64 * 2.0, 0.0));
+            }
+        }
+        let bvh = Bvh::new(&bounds);
+        assert!(bvh.nodes.len() > 1);
+
+        // a ray along z is a candidate for the box it hits, but not for most others
+        let r = Ray::new(Point::new(6, 8, -5), Vector::new(0, 0, 1));
+        let c = candidates(&bvh, &r);
+        assert!(c.contains(&34));
+        assert!(c.len() <= MAX_LEAF_SIZE);
+
+        // a ray along y is a candidate for the column of boxes at x = 4
+        let r = Ray::new(Point::new(4, -5, 0), Vector::new(0, 1, 0));
+        let c = candidates(&bvh, &r);
+        assert!((20..30).all(|i| c.contains(&i)));
+        assert!(c.len() < 50);
+
+        // a ray passing far from the grid is a candidate for nothing
+        let r = Ray::new(Point::new(5, -5, 10), Vector::new(0, 1, 0));
+        assert_eq!(candidates(&bvh, &r), vec![]);
+    }
+
+    #[test]
+    fn coincident_items() {
+        let bounds = vec![unit_at(1.0, 1.0, 1.0); 20];
+        let bvh = Bvh::new(&bounds);
+        let r = Ray::new(Point::new(1, 1, -5), Vector::new(0, 0, 1));
+        assert_eq!(candidates(&bvh, &r), (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn depth_is_limited() {
+        // boxes at exponentially increasing distances, which split one at a time
+        let bounds: Vec<_> = (0..100)
+            .map(|i| {
+                let x = 16f64.powi(i);
+                Bounds::new(Point::new(x, -1, -1), Point::new(x * 2.0, 1, 1))
+            })
+            .collect();
+        let bvh = Bvh::new(&bounds);
+        fn depth(bvh: &Bvh, n: usize) -> usize {
+            match bvh.nodes[n] {
+                BvhNode::Leaf { .. } => 0,
+                BvhNode::Interior { left, right, .. } => {
+                    1 + depth(bvh, left).max(depth(bvh, right))
+                }
+            }
+        }
+        assert_eq!(depth(&bvh, 0), MAX_DEPTH);
+
+        // a ray along the row is a candidate for every box
+        let r = Ray::new(Point::new(0, 0, 0), Vector::new(1, 0, 0));
+        assert_eq!(candidates(&bvh, &r), (0..100).collect::<Vec<_>>());
+    }
+}
@@ -1,5 +1,4 @@
-use crate::{mat4, spaces, Color, Mat, Point, Ray, Vector};
-use image::RgbImage;
+use crate::{mat4, spaces, Color, Framebuffer, Mat, Point, Ray, Vector};
 use rayon::prelude::*;
 
 /// Camera represents a view onto the world space.
@@ -44,23 +43,45 @@ impl Camera {
         up: Vector<spaces::World>,
         oversample: u32,
     ) -> Self {
-        let half_view = (fov / 2.0).tan();
+        let mut camera = Self {
+            hsize: 0,
+            vsize: 0,
+            inv_transform: Self::view_transform(from, to, up).inverse(),
+            pixel_size: 0.0,
+            half_width: 0.0,
+            half_height: 0.0,
+            oversample,
+        };
+        camera.set_size(hsize, vsize, (fov / 2.0).tan());
+        camera
+    }
+
+    /// Return an updated camera rendering an image of the given size, with the same field of
+    /// view along its longer side.
+    pub fn with_size(mut self, hsize: u32, vsize: u32) -> Self {
+        let half_view = self.half_width.max(self.half_height);
+        self.set_size(hsize, vsize, half_view);
+        self
+    }
+
+    /// Return an updated camera which oversamples each pixel the given number of times.
+    pub fn with_oversample(mut self, oversample: u32) -> Self {
+        self.oversample = oversample;
+        self
+    }
+
+    fn set_size(&mut self, hsize: u32, vsize: u32, half_view: f64) {
         let aspect = hsize as f64 / vsize as f64;
         let (half_width, half_height) = if aspect >= 1.0 {
             (half_view, half_view / aspect)
         } else {
             (half_view * aspect, half_view)
         };
-        let pixel_size = (half_width * 2.0 / hsize as f64) as f64;
-        Self {
-            hsize,
-            vsize,
-            inv_transform: Self::view_transform(from, to, up).inverse(),
-            pixel_size,
-            half_width,
-            half_height,
-            oversample,
-        }
+        self.hsize = hsize;
+        self.vsize = vsize;
+        self.half_width = half_width;
+        self.half_height = half_height;
+        self.pixel_size = half_width * 2.0 / hsize as f64;
     }
 
     fn view_transform(
@@ -103,6 +124,21 @@ impl Camera {
         Ray::new(origin, direction)
     }
 
+    /// Get the horizontal size of the image, in pixels.
+    pub fn hsize(&self) -> u32 {
+        self.hsize
+    }
+
+    /// Get the vertical size of the image, in pixels.
+    pub fn vsize(&self) -> u32 {
+        self.vsize
+    }
+
+    /// Get the number of rays cast through each pixel.
+    pub fn rays_per_pixel(&self) -> u32 {
+        self.oversample * self.oversample
+    }
+
     /// Determine the color at the given x and y coordinates of the image.
     pub fn color_at(&self, x: u32, y: u32, world: &impl RayColor, debug: bool) -> Color {
         let mut acc = Color::black();
@@ -111,39 +147,27 @@ impl Camera {
         for xo in 1..=self.oversample {
             for yo in 1..=self.oversample {
                 let ray = self.ray_for_pixel(x, y, overfactor * xo as f64, overfactor * yo as f64);
-                acc = acc + world.color_at(&ray, debug);
+                acc += world.color_at(&ray, debug);
             }
         }
 
         acc / (self.oversample * self.oversample) as f64
     }
 
-    /// Create an image
-    pub fn render(&self, world: &impl RayColor) -> RgbImage {
-        let mut img = RgbImage::new(self.hsize, self.vsize);
-        img.enumerate_rows_mut().par_bridge().for_each(|(y, row)| {
-            for (x, _, p) in row {
-                *p = self.color_at(x, y, world, false).into();
-            }
-        });
-        img
-    }
-
-    /// Render the image as a buffer of u32's
-    pub fn u32_buffer(&self, world: &impl RayColor, buf: &mut [u32]) {
-        let hsize = self.hsize as usize;
-        let vsize = self.vsize as usize;
-        debug_assert_eq!(buf.len(), hsize * vsize);
-
+    /// Render the world into a framebuffer.  Use `Framebuffer::to_image` to tone-map the
+    /// result for display.
+    pub fn render(&self, world: &impl RayColor) -> Framebuffer {
+        let mut fb = Framebuffer::new(self.hsize, self.vsize);
         // parallelize over rows
-        buf.chunks_mut(hsize)
+        fb.pixels_mut()
+            .par_chunks_mut(self.hsize as usize)
             .enumerate()
-            .par_bridge()
             .for_each(|(y, row)| {
-                for x in 0..hsize {
-                    row[x] = self.color_at(x as u32, y as u32, world, false).into();
+                for (x, p) in row.iter_mut().enumerate() {
+                    *p = self.color_at(x as u32, y as u32, world, false);
                 }
             });
+        fb
     }
 }
 
@@ -290,6 +314,24 @@ mod test {
         assert_relative_eq!(cam.pixel_size, 0.01);
     }
 
+    #[test]
+    fn camera_with_size() {
+        let cam = Camera::new(
+            200,
+            125,
+            PI / 2.0,
+            Point::new(0, 0, 0),
+            Point::new(0, 0, -1),
+            Vector::new(0, 1, 0),
+            1,
+        );
+        let cam = cam.with_size(400, 250);
+        assert_eq!((cam.hsize(), cam.vsize()), (400, 250));
+        assert_relative_eq!(cam.pixel_size, 0.005);
+        let cam = cam.with_size(125, 200);
+        assert_relative_eq!(cam.pixel_size, 0.01);
+    }
+
     #[test]
     fn ray_through_center() {
         let cam = Camera::new(
@@ -347,4 +389,25 @@ mod test {
         }
         .eq(&r.direction, &Vector::new(halfsqrt2, 0, -halfsqrt2)));
     }
+
+    #[test]
+    fn render() {
+        let w = crate::World::test_world();
+        let cam = Camera::new(
+            11,
+            11,
+            PI / 2.0,
+            Point::new(0, 0, -5),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+            1,
+        );
+        let fb = cam.render(&w);
+        assert_eq!((fb.width(), fb.height()), (11, 11));
+        assert_relative_eq!(
+            fb.get(5, 5),
+            Color::new(0.38066, 0.47583, 0.2855),
+            epsilon = 0.0001
+        );
+    }
 }
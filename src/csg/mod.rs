@@ -1,3 +1,5 @@
+mod obj;
 mod object;
 
+pub use obj::*;
 pub use object::*;
@@ -0,0 +1,505 @@
+use crate::csg::{Group, Object, SmoothTriangle, Triangle};
+use crate::{spaces, Point, Vector};
+use std::fmt;
+use std::path::Path;
+
+/// An error reading a Wavefront OBJ file.
+#[derive(Debug)]
+pub enum ObjError {
+    /// The file could not be read
+    Io(std::io::Error),
+
+    /// A line of the file could not be parsed.  Lines are numbered from 1.
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io(err) => write!(f, "{}", err),
+            ObjError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ObjError::Io(err) => Some(err),
+            ObjError::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ObjError {
+    fn from(err: std::io::Error) -> Self {
+        ObjError::Io(err)
+    }
+}
+
+/// Read a Wavefront OBJ file into an object.  See `parse_obj`.
+pub fn load_obj<P: AsRef<Path>>(path: P) -> Result<Object, ObjError> {
+    parse_obj(&std::fs::read_to_string(path)?)
+}
+
+/// Parse the contents of a Wavefront OBJ file into an object.
+///
+/// The result is a group containing a triangle for each face outside of a named group, and a
+/// child group for each named group.  Polygons are split into a fan of triangles, skipping any
+/// triangles whose vertices lie in a line, since they have no area or normal.  Faces which give
+/// a normal for every vertex become smooth triangles.
+///
+/// Vertices, vertex normals, texture coordinates, faces and groups (`v`, `vn`, `vt`, `f` and
+/// `g`) are supported; other statements are ignored.  Faces which give texture coordinates for
+/// every vertex become triangles with those coordinates, so intersections with them report the
+/// interpolated texture coordinates as their (u, v).  A missing `v` texture coordinate is 0.
+pub fn parse_obj(input: &str) -> Result<Object, ObjError> {
+    let mut parser = Parser::default();
+    for (i, line) in input.lines().enumerate() {
+        parser.parse_line(line).map_err(|message| ObjError::Parse {
+            line: i + 1,
+            message,
+        })?;
+    }
+    Ok(parser.into_object())
+}
+
+/// A triangle in an OBJ file, as indexes into its vertices, texture coordinates and normals.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Face {
+    vertices: [usize; 3],
+    texture_coords: Option<[usize; 3]>,
+    normals: Option<[usize; 3]>,
+}
+
+#[derive(Debug, Default)]
+struct Parser {
+    vertices: Vec<Point<spaces::Object>>,
+    normals: Vec<Vector<spaces::Object>>,
+    texture_coords: Vec<(f64, f64)>,
+
+    /// Faces outside of any named group
+    default_group: Vec<Face>,
+
+    /// Named groups, in the order in which they first appear
+    groups: Vec<(String, Vec<Face>)>,
+
+    /// The index in `groups` of the current group, if any
+    current_group: Option<usize>,
+}
+
+impl Parser {
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        // strip comments
+        let line = line.split('#').next().unwrap_or("");
+        let mut words = line.split_whitespace();
+        let keyword = words.next();
+        let args: Vec<&str> = words.collect();
+        match keyword {
+            Some("v") => {
+                if args.len() != 3 && args.len() != 4 {
+                    return Err(format!(
+                        "expected 3 or 4 values for vertex, got {}",
+                        args.len()
+                    ));
+                }
+                let [x, y, z] = parse_floats(&args[..3])?;
+                self.vertices.push(Point::new(x, y, z));
+            }
+            Some("vn") => {
+                if args.len() != 3 {
+                    return Err(format!("expected 3 values for normal, got {}", args.len()));
+                }
+                let [x, y, z] = parse_floats(&args)?;
+                self.normals.push(Vector::new(x, y, z));
+            }
+            Some("vt") => {
+                if args.is_empty() || args.len() > 3 {
+                    return Err(format!(
+                        "expected 1 to 3 values for texture coordinate, got {}",
+                        args.len()
+                    ));
+                }
+                let mut uvw = [0.0; 3];
+                for (c, arg) in uvw.iter_mut().zip(args) {
+                    *c = parse_float(arg)?;
+                }
+                self.texture_coords.push((uvw[0], uvw[1]));
+            }
+            Some("f") => self.parse_face(&args)?,
+            Some("g") => {
+                let name = args.join(" ");
+                if name.is_empty() {
+                    self.current_group = None;
+                } else if let Some(i) = self.groups.iter().position(|(n, _)| *n == name) {
+                    self.current_group = Some(i);
+                } else {
+                    self.groups.push((name, vec![]));
+                    self.current_group = Some(self.groups.len() - 1);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn parse_face(&mut self, args: &[&str]) -> Result<(), String> {
+        if args.len() < 3 {
+            return Err(format!(
+                "expected at least 3 vertices for face, got {}",
+                args.len()
+            ));
+        }
+
+        let mut vertices = Vec::with_capacity(args.len());
+        let mut texture_coords = Vec::with_capacity(args.len());
+        let mut normals = Vec::with_capacity(args.len());
+        for arg in args {
+            let mut parts = arg.split('/');
+            let vertex = parts.next().unwrap_or("");
+            let texcoord = parts.next().unwrap_or("");
+            let normal = parts.next().unwrap_or("");
+            if parts.next().is_some() {
+                return Err(format!("invalid face vertex {:?}", arg));
+            }
+
+            vertices.push(resolve_index(vertex, self.vertices.len(), "vertex")?);
+            if !texcoord.is_empty() {
+                texture_coords.push(resolve_index(
+                    texcoord,
+                    self.texture_coords.len(),
+                    "texture coordinate",
+                )?);
+            }
+            if !normal.is_empty() {
+                normals.push(resolve_index(normal, self.normals.len(), "normal")?);
+            }
+        }
+        let textured = texture_coords.len() == vertices.len();
+        let smooth = normals.len() == vertices.len();
+
+        // split the polygon into a fan of triangles around the first vertex
+        let faces = match self.current_group {
+            Some(i) => &mut self.groups[i].1,
+            None => &mut self.default_group,
+        };
+        for i in 1..vertices.len() - 1 {
+            let [p1, p2, p3] =
+                [vertices[0], vertices[i], vertices[i + 1]].map(|v| self.vertices[v]);
+            if (p2 - p1).cross(p3 - p1).magnitude() == 0.0 {
+                continue;
+            }
+            faces.push(Face {
+                vertices: [vertices[0], vertices[i], vertices[i + 1]],
+                texture_coords: textured
+                    .then(|| [texture_coords[0], texture_coords[i], texture_coords[i + 1]]),
+                normals: smooth.then(|| [normals[0], normals[i], normals[i + 1]]),
+            });
+        }
+        Ok(())
+    }
+
+    fn triangle(&self, face: &Face) -> Object {
+        let [p1, p2, p3] = face.vertices.map(|i| self.vertices[i]);
+        let uvs = face
+            .texture_coords
+            .map(|t| t.map(|i| self.texture_coords[i]));
+        match (face.normals, uvs) {
+            (Some(normals), uvs) => {
+                let [n1, n2, n3] = normals.map(|i| self.normals[i]);
+                let t = SmoothTriangle::new(p1, p2, p3, n1, n2, n3);
+                match uvs {
+                    Some([uv1, uv2, uv3]) => Object::new(t.with_texture_coords(uv1, uv2, uv3)),
+                    None => Object::new(t),
+                }
+            }
+            (None, Some([uv1, uv2, uv3])) => {
+                Object::new(Triangle::new(p1, p2, p3).with_texture_coords(uv1, uv2, uv3))
+            }
+            (None, None) => Object::new(Triangle::new(p1, p2, p3)),
+        }
+    }
+
+    fn into_object(self) -> Object {
+        let mut group = Group::new();
+        for face in &self.default_group {
+            group.add_child(self.triangle(face));
+        }
+        for (_, faces) in &self.groups {
+            if faces.is_empty() {
+                continue;
+            }
+            let mut child = Group::new();
+            for face in faces {
+                child.add_child(self.triangle(face));
+            }
+            group.add_child(Object::group(child));
+        }
+        Object::group(group)
+    }
+}
+
+fn parse_float(s: &str) -> Result<f64, String> {
+    s.parse().map_err(|_| format!("invalid number {:?}", s))
+}
+
+fn parse_floats<const N: usize>(args: &[&str]) -> Result<[f64; N], String> {
+    let mut result = [0.0; N];
+    for (r, arg) in result.iter_mut().zip(args) {
+        *r = parse_float(arg)?;
+    }
+    Ok(result)
+}
+
+/// Convert a 1-based index, or a negative index counting back from the most recent element,
+/// to a 0-based index into a list of `len` elements.
+fn resolve_index(s: &str, len: usize, what: &str) -> Result<usize, String> {
+    let i: isize = s
+        .parse()
+        .map_err(|_| format!("invalid {} index {:?}", what, s))?;
+    let resolved = if i > 0 {
+        i as usize - 1
+    } else if i < 0 && i.unsigned_abs() <= len {
+        len - i.unsigned_abs()
+    } else {
+        return Err(format!("invalid {} index {}", what, i));
+    };
+    if resolved >= len {
+        return Err(format!(
+            "{} index {} out of range; only {} defined",
+            what, i, len
+        ));
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::*;
+    use approx::*;
+
+    fn parse(input: &str) -> Parser {
+        let mut parser = Parser::default();
+        for line in input.lines() {
+            parser.parse_line(line).unwrap();
+        }
+        parser
+    }
+
+    fn parse_err(input: &str) -> (usize, String) {
+        match parse_obj(input) {
+            Err(ObjError::Parse { line, message }) => (line, message),
+            r => panic!("expected parse error, got {:?}", r),
+        }
+    }
+
+    fn face(vertices: [usize; 3]) -> Face {
+        Face {
+            vertices,
+            texture_coords: None,
+            normals: None,
+        }
+    }
+
+    #[test]
+    fn ignore_unrecognized() {
+        let p = parse(
+            "There was a young lady named Bright\n\
+             who traveled much faster than light.\n\
+             # comment\n\
+             \n\
+             usemtl shiny\n",
+        );
+        assert!(p.vertices.is_empty());
+        assert!(p.default_group.is_empty());
+    }
+
+    #[test]
+    fn vertices() {
+        let p = parse("v -1 1 0\nv -1.0000 0.5000 0.0000\nv 1 0 0\nv 1 1 0 1.0\n");
+        assert_eq!(p.vertices.len(), 4);
+        assert_relative_eq!(p.vertices[0], Point::new(-1, 1, 0));
+        assert_relative_eq!(p.vertices[1], Point::new(-1, 0.5, 0));
+        assert_relative_eq!(p.vertices[2], Point::new(1, 0, 0));
+        assert_relative_eq!(p.vertices[3], Point::new(1, 1, 0));
+    }
+
+    #[test]
+    fn faces() {
+        let p = parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\nf 1 2 3\nf 1 3 4\n");
+        assert_eq!(p.default_group, vec![face([0, 1, 2]), face([0, 2, 3])]);
+    }
+
+    #[test]
+    fn polygons() {
+        let p = parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\nv 0 2 0\nf 1 2 3 4 5\n");
+        assert_eq!(
+            p.default_group,
+            vec![face([0, 1, 2]), face([0, 2, 3]), face([0, 3, 4])]
+        );
+    }
+
+    #[test]
+    fn degenerate_faces() {
+        // the second vertex lies between the first and third, and the fourth repeats the first
+        let p = parse(
+            "v 0 0 0\nv 1 0 0\nv 2 0 0\nv 2 1 0\n\
+             f 1 2 3\nf 1 2 3 4\nf 1 4 1\n",
+        );
+        assert_eq!(p.default_group, vec![face([0, 2, 3])]);
+    }
+
+    #[test]
+    fn negative_indices() {
+        let p = parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nf -3 -2 -1\n");
+        assert_eq!(p.default_group, vec![face([0, 1, 2])]);
+    }
+
+    #[test]
+    fn groups() {
+        let p = parse(
+            "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\
+             g FirstGroup\nf 1 2 3\n\
+             g SecondGroup\nf 1 3 4\n\
+             g FirstGroup\nf 2 3 4\n",
+        );
+        assert!(p.default_group.is_empty());
+        assert_eq!(p.groups.len(), 2);
+        assert_eq!(p.groups[0].0, "FirstGroup");
+        assert_eq!(p.groups[0].1, vec![face([0, 1, 2]), face([1, 2, 3])]);
+        assert_eq!(p.groups[1].0, "SecondGroup");
+        assert_eq!(p.groups[1].1, vec![face([0, 2, 3])]);
+    }
+
+    #[test]
+    fn normals() {
+        let p = parse("vn 0 0 1\nvn 0.707 0 -0.707\nvn 1 2 3\n");
+        assert_eq!(p.normals.len(), 3);
+        assert_relative_eq!(p.normals[0], Vector::new(0, 0, 1));
+        assert_relative_eq!(p.normals[1], Vector::new(0.707, 0, -0.707));
+        assert_relative_eq!(p.normals[2], Vector::new(1, 2, 3));
+    }
+
+    #[test]
+    fn faces_with_normals() {
+        let p = parse(
+            "v 0 1 0\nv -1 0 0\nv 1 0 0\n\
+             vn -1 0 0\nvn 1 0 0\nvn 0 1 0\n\
+             vt 0 0\n\
+             f 1//3 2//1 3//2\n\
+             f 1/1/3 2/1/1 3/1/2\n\
+             f 1/1 2/1 3/1\n",
+        );
+        let smooth = Face {
+            vertices: [0, 1, 2],
+            texture_coords: None,
+            normals: Some([2, 0, 1]),
+        };
+        let textured = Face {
+            vertices: [0, 1, 2],
+            texture_coords: Some([0, 0, 0]),
+            normals: None,
+        };
+        assert_eq!(
+            p.default_group,
+            vec![
+                smooth,
+                Face {
+                    texture_coords: Some([0, 0, 0]),
+                    ..smooth
+                },
+                textured
+            ]
+        );
+    }
+
+    #[test]
+    fn texture_coords() {
+        let obj = parse_obj(
+            "v 0 1 0\nv -1 0 0\nv 1 0 0\n\
+             vt 0.5 1\nvt 0 0\nvt 1\n\
+             vn 0 0 -1\n\
+             f 1/1 2/2 3/3\n\
+             g Smooth\nf 1/1/1 2/2/1 3/3/1\n",
+        )
+        .unwrap();
+        let mut w = World::default();
+        let tris = w.add_group(obj).unwrap();
+        let r = Ray::new(Point::new(-0.2, 0.3, -2), Vector::new(0, 0, 1));
+        for t in tris.iter() {
+            let mut inters = Intersections::default();
+            w[t].intersect(t, &r, &mut inters);
+            let (u, v) = inters.iter().next().unwrap().uv.unwrap();
+            assert_relative_eq!(u, 0.4, epsilon = 1e-9);
+            assert_relative_eq!(v, 0.3, epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn into_world() {
+        let obj = parse_obj(
+            "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\
+             f 1 2 3\n\
+             g Square\nf 1 2 3 4\n",
+        )
+        .unwrap();
+        let mut w = World::default();
+        w.add_object(obj);
+        let r = Ray::new(Point::new(0.5, 0.8, -5), Vector::new(0, 0, 1));
+        let mut inters = Intersections::default();
+        w.intersect(&r, &mut inters);
+        // the square is hit; the triangle outside any group is not
+        assert_eq!(inters.len(), 1);
+        assert_relative_eq!(inters.iter().next().unwrap().t, 5.0);
+    }
+
+    #[test]
+    fn malformed_vertex() {
+        assert_eq!(
+            parse_err("v 1 2 3\nv 1 two 3\n"),
+            (2, "invalid number \"two\"".to_string())
+        );
+        assert_eq!(
+            parse_err("v 1 2\n"),
+            (1, "expected 3 or 4 values for vertex, got 2".to_string())
+        );
+    }
+
+    #[test]
+    fn malformed_face() {
+        let vertices = "v -1 1 0\nv -1 0 0\nv 1 0 0\n";
+        assert_eq!(
+            parse_err(&format!("{}f 1 2\n", vertices)),
+            (
+                4,
+                "expected at least 3 vertices for face, got 2".to_string()
+            )
+        );
+        assert_eq!(
+            parse_err(&format!("{}f 1 2 4\n", vertices)),
+            (4, "vertex index 4 out of range; only 3 defined".to_string())
+        );
+        assert_eq!(
+            parse_err(&format!("{}f 1 0 2\n", vertices)),
+            (4, "invalid vertex index 0".to_string())
+        );
+        assert_eq!(
+            parse_err(&format!("{}f 1//1 2//1 3//1\n", vertices)),
+            (4, "normal index 1 out of range; only 0 defined".to_string())
+        );
+        assert_eq!(
+            parse_err(&format!("{}f 1/x 2 3\n", vertices)),
+            (4, "invalid texture coordinate index \"x\"".to_string())
+        );
+    }
+
+    #[test]
+    fn error_display() {
+        let err = parse_obj("f 1 2 3\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1: vertex index 1 out of range; only 0 defined"
+        );
+    }
+}
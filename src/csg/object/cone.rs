@@ -0,0 +1,266 @@
+use crate::csg::ObjectInner;
+use crate::{spaces, Bounds, Intersections, ObjectIndex, Point, Ray, Vector};
+
+const EPSILON: f64 = 0.00001;
+
+/// A double-napped cone around the y axis, with its apex at the origin and a radius equal to
+/// the distance from the x-z plane.  It extends from `minimum` to `maximum` (exclusive) along
+/// the y axis.  If closed, the ends of the cone are capped.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Cone {
+    minimum: f64,
+    maximum: f64,
+    closed: bool,
+}
+
+impl Default for Cone {
+    /// The default cone is infinitely long.
+    fn default() -> Self {
+        Self {
+            minimum: f64::NEG_INFINITY,
+            maximum: f64::INFINITY,
+            closed: false,
+        }
+    }
+}
+
+impl Cone {
+    /// Create a new cone with the given extent along the y axis.
+    pub fn new(minimum: f64, maximum: f64, closed: bool) -> Self {
+        Self {
+            minimum,
+            maximum,
+            closed,
+        }
+    }
+
+    fn intersect_caps(
+        &self,
+        object_index: ObjectIndex,
+        ray: Ray<spaces::Object>,
+        inters: &mut Intersections,
+    ) {
+        // check whether the intersection at t is within the radius of the cone at y
+        fn check_cap(ray: Ray<spaces::Object>, t: f64, y: f64) -> bool {
+            let x = ray.origin.x + t * ray.direction.x;
+            let z = ray.origin.z + t * ray.direction.z;
+            x * x + z * z <= y * y
+        }
+
+        if !self.closed || ray.direction.y.abs() < EPSILON {
+            return;
+        }
+
+        for y in [self.minimum, self.maximum] {
+            let t = (y - ray.origin.y) / ray.direction.y;
+            if check_cap(ray, t, y) {
+                inters.add(t, object_index);
+            }
+        }
+    }
+
+    fn add_if_in_extent(
+        &self,
+        t: f64,
+        object_index: ObjectIndex,
+        ray: Ray<spaces::Object>,
+        inters: &mut Intersections,
+    ) {
+        let y = ray.origin.y + t * ray.direction.y;
+        if self.minimum < y && y < self.maximum {
+            inters.add(t, object_index);
+        }
+    }
+}
+
+impl ObjectInner for Cone {
+    fn intersect(
+        &self,
+        object_index: ObjectIndex,
+        ray: Ray<spaces::Object>,
+        inters: &mut Intersections,
+    ) {
+        let (o, d) = (ray.origin, ray.direction);
+        let a = d.x * d.x - d.y * d.y + d.z * d.z;
+        let b = 2.0 * (o.x * d.x - o.y * d.y + o.z * d.z);
+        let c = o.x * o.x - o.y * o.y + o.z * o.z;
+
+        if a.abs() < EPSILON {
+            // the ray is parallel to one half of the cone, so hits the other half at most once
+            if b.abs() >= EPSILON {
+                self.add_if_in_extent(-c / (2.0 * b), object_index, ray, inters);
+            }
+        } else {
+            let discriminant = b * b - 4.0 * a * c;
+            if discriminant >= 0.0 {
+                let sqrt = discriminant.sqrt();
+                self.add_if_in_extent((-b - sqrt) / (a * 2.0), object_index, ray, inters);
+                self.add_if_in_extent((-b + sqrt) / (a * 2.0), object_index, ray, inters);
+            }
+        }
+
+        self.intersect_caps(object_index, ray, inters);
+    }
+
+    fn normal(&self, point: Point<spaces::Object>) -> Vector<spaces::Object> {
+        let dist = point.x * point.x + point.z * point.z;
+        if point.y >= self.maximum - EPSILON && dist < self.maximum * self.maximum {
+            Vector::new(0, 1, 0)
+        } else if point.y <= self.minimum + EPSILON && dist < self.minimum * self.minimum {
+            Vector::new(0, -1, 0)
+        } else {
+            let y = dist.sqrt();
+            Vector::new(point.x, if point.y > 0.0 { -y } else { y }, point.z)
+        }
+    }
+
+    fn bounds(&self) -> Bounds<spaces::Object> {
+        let r = self.minimum.abs().max(self.maximum.abs());
+        Bounds::new(
+            Point::new(-r, self.minimum, -r),
+            Point::new(r, self.maximum, r),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::csg::*;
+    use crate::*;
+    use approx::*;
+
+    macro_rules! test_intersect {
+        ($name:ident, $cone:expr, $origin:expr, $direction:expr, $($t:expr),*) => {
+            #[test]
+            fn $name() {
+                let ray = Ray::new($origin, $direction.normalize());
+                let oi = ObjectIndex::test_value(7);
+                let mut inters = Intersections::default();
+                $cone.intersect(oi, ray, &mut inters);
+                let mut it = inters.iter();
+                $(
+                    assert_relative_eq!(
+                        it.next().expect("expected intersection").t,
+                        $t,
+                        epsilon = 0.00001
+                    );
+                )*
+                assert!(it.next().is_none());
+            }
+        };
+    }
+
+    test_intersect!(
+        hit_apex,
+        Cone::default(),
+        Point::new(0, 0, -5),
+        Vector::new(0, 0, 1),
+        5.0,
+        5.0
+    );
+    test_intersect!(
+        hit_oblique,
+        Cone::default(),
+        Point::new(0, 0, -5),
+        Vector::new(1, 1, 1),
+        8.66025,
+        8.66025
+    );
+    test_intersect!(
+        hit_both_halves,
+        Cone::default(),
+        Point::new(1, 1, -5),
+        Vector::new(-0.5, -1, 1),
+        4.55006,
+        49.44994
+    );
+    test_intersect!(
+        parallel_to_half,
+        Cone::default(),
+        Point::new(0, 0, -1),
+        Vector::new(0, 1, 1),
+        0.35355
+    );
+
+    fn closed() -> Cone {
+        Cone::new(-0.5, 0.5, true)
+    }
+
+    macro_rules! test_count {
+        ($name:ident, $cone:expr, $origin:expr, $direction:expr, $count:expr) => {
+            #[test]
+            fn $name() {
+                let ray = Ray::new($origin, $direction.normalize());
+                let oi = ObjectIndex::test_value(7);
+                let mut inters = Intersections::default();
+                $cone.intersect(oi, ray, &mut inters);
+                assert_eq!(inters.len(), $count);
+            }
+        };
+    }
+
+    test_count!(
+        caps_miss,
+        closed(),
+        Point::new(0, 0, -5),
+        Vector::new(0, 1, 0),
+        0
+    );
+    test_count!(
+        caps_oblique,
+        closed(),
+        Point::new(0, 0, -0.25),
+        Vector::new(0, 1, 1),
+        2
+    );
+    test_count!(
+        caps_vertical,
+        closed(),
+        Point::new(0, 0, -0.25),
+        Vector::new(0, 1, 0),
+        4
+    );
+
+    macro_rules! test_norm {
+        ($name:ident, $cone:expr, $point:expr, $normal:expr) => {
+            #[test]
+            fn $name() {
+                let normal = $cone.normal($point);
+                assert_relative_eq!(normal, $normal);
+            }
+        };
+    }
+
+    test_norm!(
+        norm_upper,
+        Cone::default(),
+        Point::new(1, 1, 1),
+        Vector::new(1, -(2f64.sqrt()), 1)
+    );
+    test_norm!(
+        norm_lower,
+        Cone::default(),
+        Point::new(-1, -1, 0),
+        Vector::new(-1, 1, 0)
+    );
+    test_norm!(
+        norm_top_cap,
+        closed(),
+        Point::new(0.2, 0.5, 0.1),
+        Vector::new(0, 1, 0)
+    );
+    test_norm!(
+        norm_bottom_cap,
+        closed(),
+        Point::new(-0.2, -0.5, 0.1),
+        Vector::new(0, -1, 0)
+    );
+
+    #[test]
+    fn bounds() {
+        let b = Cone::new(-2.0, 1.0, true).bounds();
+        assert_relative_eq!(b.min, Point::new(-2, -2, -2));
+        assert_relative_eq!(b.max, Point::new(2, 1, 2));
+        assert!(!Cone::default().bounds().is_finite());
+    }
+}
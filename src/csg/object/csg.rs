@@ -0,0 +1,280 @@
+use crate::csg::Object;
+
+/// A constructive solid geometry operation, combining two objects.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CsgOp {
+    /// Everything in either object
+    Union,
+    /// Everything in both objects
+    Intersection,
+    /// Everything in the left object but not in the right object
+    Difference,
+}
+
+impl CsgOp {
+    /// Determine whether an intersection is part of the combined object's surface, given
+    /// whether it is on the left object, and whether the ray is currently inside the left
+    /// and right objects.
+    pub(crate) fn allowed(self, left_hit: bool, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOp::Union => (left_hit && !in_right) || (!left_hit && !in_left),
+            CsgOp::Intersection => (left_hit && in_right) || (!left_hit && in_left),
+            CsgOp::Difference => (left_hit && !in_right) || (!left_hit && in_left),
+        }
+    }
+}
+
+/// A combination of two objects by a CSG operation.
+///
+/// Use `Object::csg` to make this into an object which can be transformed, given a material,
+/// or added to a world or group.  As with groups, the transform and material apply to both
+/// children.  The surface of the combined object retains the material of the child on which
+/// it lies.
+///
+/// Both children should be closed solids, so that each ray enters and exits them in pairs.
+#[derive(Debug)]
+pub struct Csg {
+    pub(crate) op: CsgOp,
+    pub(crate) left: Object,
+    pub(crate) right: Object,
+}
+
+impl Csg {
+    /// Combine two objects with the given operation.
+    pub fn new(op: CsgOp, left: Object, right: Object) -> Self {
+        Self { op, left, right }
+    }
+
+    /// Combine two objects into the union of both.
+    pub fn union(left: Object, right: Object) -> Self {
+        Self::new(CsgOp::Union, left, right)
+    }
+
+    /// Combine two objects into the intersection of both.
+    pub fn intersection(left: Object, right: Object) -> Self {
+        Self::new(CsgOp::Intersection, left, right)
+    }
+
+    /// Combine two objects into the left object with the right object removed.
+    pub fn difference(left: Object, right: Object) -> Self {
+        Self::new(CsgOp::Difference, left, right)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::csg::*;
+    use crate::*;
+    use approx::*;
+
+    macro_rules! test_allowed {
+        ($name:ident, $op:expr, $expected:expr) => {
+            #[test]
+            fn $name() {
+                let mut results = vec![];
+                for left_hit in [true, false] {
+                    for in_left in [true, false] {
+                        for in_right in [true, false] {
+                            results.push($op.allowed(left_hit, in_left, in_right));
+                        }
+                    }
+                }
+                assert_eq!(results, $expected);
+            }
+        };
+    }
+
+    test_allowed!(
+        union_allowed,
+        CsgOp::Union,
+        vec![false, true, false, true, false, false, true, true]
+    );
+    test_allowed!(
+        intersection_allowed,
+        CsgOp::Intersection,
+        vec![true, false, true, false, true, true, false, false]
+    );
+    test_allowed!(
+        difference_allowed,
+        CsgOp::Difference,
+        vec![false, true, false, true, true, true, false, false]
+    );
+
+    fn hits(csg: Csg, ray: Ray<spaces::World>) -> Vec<(f64, ObjectIndex)> {
+        let mut w = World::default();
+        w.add_object(Object::csg(csg));
+        let mut inters = Intersections::default();
+        w.intersect(&ray, &mut inters);
+        inters.iter().map(|i| (i.t, i.object_index)).collect()
+    }
+
+    /// A sphere and a cube overlapping on the z axis, with a ray passing through both.
+    fn sphere_and_cube(op: CsgOp) -> Vec<(f64, ObjectIndex)> {
+        hits(
+            Csg::new(
+                op,
+                Object::new(Sphere),
+                Object::new(Cube).with_transform(Mat::identity().translate(0, 0, 1.5)),
+            ),
+            Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1)),
+        )
+    }
+
+    const SPHERE: usize = 0;
+    const CUBE: usize = 1;
+
+    fn assert_hits(hits: Vec<(f64, ObjectIndex)>, expected: &[(f64, usize)]) {
+        assert_eq!(hits.len(), expected.len(), "{:?}", hits);
+        for ((t, idx), (exp_t, exp_idx)) in hits.iter().zip(expected) {
+            assert_relative_eq!(*t, *exp_t);
+            assert_eq!(*idx, ObjectIndex::test_value(*exp_idx));
+        }
+    }
+
+    #[test]
+    fn filter_union() {
+        assert_hits(sphere_and_cube(CsgOp::Union), &[(4.0, SPHERE), (7.5, CUBE)]);
+    }
+
+    #[test]
+    fn filter_intersection() {
+        assert_hits(
+            sphere_and_cube(CsgOp::Intersection),
+            &[(5.5, CUBE), (6.0, SPHERE)],
+        );
+    }
+
+    #[test]
+    fn filter_difference() {
+        assert_hits(
+            sphere_and_cube(CsgOp::Difference),
+            &[(4.0, SPHERE), (5.5, CUBE)],
+        );
+    }
+
+    #[test]
+    fn ray_misses() {
+        let h = hits(
+            Csg::union(Object::new(Sphere), Object::new(Cube)),
+            Ray::new(Point::new(0, 2, -5), Vector::new(0, 0, 1)),
+        );
+        assert!(h.is_empty());
+    }
+
+    #[test]
+    fn ray_hits() {
+        let h = hits(
+            Csg::union(
+                Object::new(Sphere),
+                Object::new(Sphere).with_transform(Mat::identity().translate(0, 0, 0.5)),
+            ),
+            Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1)),
+        );
+        assert_hits(h, &[(4.0, 0), (6.5, 1)]);
+    }
+
+    #[test]
+    fn nested_with_groups() {
+        // (group of two spheres) - (cube at the center), on a transformed CSG
+        let h = hits(
+            Csg::difference(
+                Object::group(
+                    Group::new()
+                        .with_child(
+                            Object::new(Sphere).with_transform(Mat::identity().translate(0, 0, -1)),
+                        )
+                        .with_child(
+                            Object::new(Sphere).with_transform(Mat::identity().translate(0, 0, 1)),
+                        ),
+                ),
+                Object::csg(Csg::intersection(
+                    Object::new(Cube).with_transform(Mat::identity().scale(0.5, 0.5, 0.5)),
+                    Object::new(Sphere),
+                )),
+            ),
+            Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1)),
+        );
+        // the spheres span -2..2 on the z axis, with a hole from -0.5..0.5
+        assert_hits(h, &[(3.0, 0), (4.5, 2), (5.5, 2), (7.0, 1)]);
+    }
+
+    #[test]
+    fn shaded_with_hit_child_material() {
+        // a white sphere with a red cube carved out of its front
+        let mut w = World::new(Light::new_point(Point::new(0, 0, -10), Color::white()));
+        w.add_object(Object::csg(Csg::difference(
+            Object::new(Sphere).with_material(Material::default().with_ambient(1.0)),
+            Object::new(Cube)
+                .with_transform(Mat::identity().scale(0.5, 0.5, 0.5).translate(0, 0, -1))
+                .with_material(
+                    Material::default()
+                        .with_color(Color::new(1, 0, 0))
+                        .with_ambient(1.0)
+                        .with_diffuse(0.0)
+                        .with_specular(0.0),
+                ),
+        )));
+
+        // through the center, the ray hits the cube's back face
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+        assert_relative_eq!(w.color_at(&r, 1.0, false), Color::new(1, 0, 0));
+
+        // away from the center, the ray hits the sphere
+        let r = Ray::new(Point::new(0, 0.75, -5), Vector::new(0, 0, 1));
+        let c = w.color_at(&r, 1.0, false);
+        assert!(c.green >= 1.0);
+    }
+
+    #[test]
+    fn leaving_through_another_child() {
+        // a lens, with a ray starting inside it
+        let mut w = World::default();
+        w.add_object(Object::csg(Csg::intersection(
+            Object::new(Sphere).with_transform(Mat::identity().translate(0, 0, 0.5)),
+            Object::new(Sphere).with_transform(Mat::identity().translate(0, 0, -0.5)),
+        )));
+        let r = Ray::new(Point::new(0, 0, 0), Vector::new(0, 0, 1));
+        let mut inters = Intersections::default();
+        w.intersect(&r, &mut inters);
+        // the ray entered through the front of the first sphere, and leaves through the
+        // back of the second, to the air outside
+        let (from_obj, hit, to_obj) = inters.hit();
+        assert_eq!(from_obj, Some(ObjectIndex::test_value(0)));
+        let hit = hit.unwrap();
+        assert_relative_eq!(hit.t, 0.5);
+        assert_eq!(hit.object_index, ObjectIndex::test_value(1));
+        assert_eq!(to_obj, None);
+    }
+
+    #[test]
+    fn refraction_through_glass() {
+        // a slab of glass from y = 0 to y = 1, made as the intersection of two cubes, which a
+        // ray enters through the top of one and leaves through the bottom of the other
+        let mut w = World::default();
+        w.add_object(
+            Object::csg(Csg::intersection(
+                Object::new(Cube).with_transform(Mat::identity().scale(10, 1, 10)),
+                Object::new(Cube)
+                    .with_transform(Mat::identity().scale(10, 1, 10).translate(0, 1, 0)),
+            ))
+            .with_material(Material::default().with_transparency(0.9, 1.5)),
+        );
+        let direction = Vector::new(1, -2, 0).normalize();
+        let mut ray = Ray::new(Point::new(0, 3, 0), direction);
+        for _ in 0..2 {
+            let mut inters = Intersections::default();
+            w.intersect(&ray, &mut inters);
+            let (from_obj, hit, to_obj) = inters.hit();
+            let n1 = from_obj.map_or(1.0, |i| w[i].material().refractive_index);
+            let n2 = to_obj.map_or(1.0, |i| w[i].material().refractive_index);
+            let hit = hit.unwrap();
+            let (point, _, normalv) = w[hit.object_index].surface_at(&hit, &ray);
+            let refracted = Material::refracted_direction(n1, n2, -ray.direction, normalv)
+                .unwrap()
+                .normalize();
+            ray = Ray::new(point + refracted * 0.01, refracted);
+        }
+        // light leaves a slab parallel to the way it entered
+        assert_relative_eq!(ray.direction, direction, epsilon = 1e-9);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::csg::ObjectInner;
-use crate::{spaces, Intersections, ObjectIndex, Point, Ray, Vector};
+use crate::{spaces, Bounds, Intersections, ObjectIndex, Point, Ray, Vector};
 
 /// A plane in x-z
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -59,6 +59,29 @@ impl ObjectInner for Cube {
             }
         }
     }
+
+    fn bounds(&self) -> Bounds<spaces::Object> {
+        Bounds::new(Point::new(-1, -1, -1), Point::new(1, 1, 1))
+    }
+
+    fn surface_area(&self) -> Option<f64> {
+        Some(24.0)
+    }
+
+    fn sample_surface(&self, u: f64, v: f64) -> (Point<spaces::Object>, Vector<spaces::Object>) {
+        // choose one of the six faces, and reuse the rest of u within it
+        let face = ((u * 6.0) as usize).min(5);
+        let a = 2.0 * (u * 6.0 - face as f64) - 1.0;
+        let b = 2.0 * v - 1.0;
+        match face {
+            0 => (Point::new(1, a, b), Vector::new(1, 0, 0)),
+            1 => (Point::new(-1, a, b), Vector::new(-1, 0, 0)),
+            2 => (Point::new(a, 1, b), Vector::new(0, 1, 0)),
+            3 => (Point::new(a, -1, b), Vector::new(0, -1, 0)),
+            4 => (Point::new(a, b, 1), Vector::new(0, 0, 1)),
+            _ => (Point::new(a, b, -1), Vector::new(0, 0, -1)),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -148,4 +171,21 @@ mod test {
         Point::new(-1, -1, -1),
         Vector::new(-1, 0, 0)
     );
+
+    #[test]
+    fn sample_surface() {
+        assert_relative_eq!(Cube.surface_area().unwrap(), 24.0);
+        let mut faces = [0; 6];
+        for i in 0..60 {
+            let (p, n) = Cube.sample_surface((i as f64 + 0.5) / 60.0, 0.3);
+            assert_relative_eq!(p.x.abs().max(p.y.abs()).max(p.z.abs()), 1.0);
+            assert_relative_eq!(n, Cube.normal(p));
+            let face = [n.x, -n.x, n.y, -n.y, n.z, -n.z]
+                .iter()
+                .position(|&c| c == 1.0)
+                .unwrap();
+            faces[face] += 1;
+        }
+        assert_eq!(faces, [10; 6]);
+    }
 }
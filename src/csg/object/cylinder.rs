@@ -0,0 +1,409 @@
+use crate::csg::ObjectInner;
+use crate::{spaces, Bounds, Intersections, ObjectIndex, Point, Ray, Vector};
+use std::f64::consts::PI;
+
+const EPSILON: f64 = 0.00001;
+
+/// A cylinder of radius 1 around the y axis, extending from `minimum` to `maximum` (exclusive)
+/// along that axis.  If closed, the ends of the cylinder are capped.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Cylinder {
+    minimum: f64,
+    maximum: f64,
+    closed: bool,
+}
+
+impl Default for Cylinder {
+    /// The default cylinder is infinitely long.
+    fn default() -> Self {
+        Self {
+            minimum: f64::NEG_INFINITY,
+            maximum: f64::INFINITY,
+            closed: false,
+        }
+    }
+}
+
+impl Cylinder {
+    /// Create a new cylinder with the given extent along the y axis.
+    pub fn new(minimum: f64, maximum: f64, closed: bool) -> Self {
+        Self {
+            minimum,
+            maximum,
+            closed,
+        }
+    }
+
+    fn intersect_caps(
+        &self,
+        object_index: ObjectIndex,
+        ray: Ray<spaces::Object>,
+        inters: &mut Intersections,
+    ) {
+        // check whether the intersection at t is within the radius of the cylinder
+        fn check_cap(ray: Ray<spaces::Object>, t: f64) -> bool {
+            let x = ray.origin.x + t * ray.direction.x;
+            let z = ray.origin.z + t * ray.direction.z;
+            x * x + z * z <= 1.0
+        }
+
+        if !self.closed || ray.direction.y.abs() < EPSILON {
+            return;
+        }
+
+        for y in [self.minimum, self.maximum] {
+            let t = (y - ray.origin.y) / ray.direction.y;
+            if check_cap(ray, t) {
+                inters.add(t, object_index);
+            }
+        }
+    }
+}
+
+impl ObjectInner for Cylinder {
+    fn intersect(
+        &self,
+        object_index: ObjectIndex,
+        ray: Ray<spaces::Object>,
+        inters: &mut Intersections,
+    ) {
+        let a = ray.direction.x * ray.direction.x + ray.direction.z * ray.direction.z;
+
+        // a ray parallel to the y axis can only hit the caps
+        if a.abs() >= EPSILON {
+            let b = 2.0 * (ray.origin.x * ray.direction.x + ray.origin.z * ray.direction.z);
+            let c = ray.origin.x * ray.origin.x + ray.origin.z * ray.origin.z - 1.0;
+            let discriminant = b * b - 4.0 * a * c;
+
+            if discriminant < 0.0 {
+                return;
+            }
+
+            let sqrt = discriminant.sqrt();
+            for t in [(-b - sqrt) / (a * 2.0), (-b + sqrt) / (a * 2.0)] {
+                let y = ray.origin.y + t * ray.direction.y;
+                if self.minimum < y && y < self.maximum {
+                    inters.add(t, object_index);
+                }
+            }
+        }
+
+        self.intersect_caps(object_index, ray, inters);
+    }
+
+    fn normal(&self, point: Point<spaces::Object>) -> Vector<spaces::Object> {
+        let dist = point.x * point.x + point.z * point.z;
+        if dist < 1.0 && point.y >= self.maximum - EPSILON {
+            Vector::new(0, 1, 0)
+        } else if dist < 1.0 && point.y <= self.minimum + EPSILON {
+            Vector::new(0, -1, 0)
+        } else {
+            Vector::new(point.x, 0, point.z)
+        }
+    }
+
+    fn bounds(&self) -> Bounds<spaces::Object> {
+        Bounds::new(
+            Point::new(-1, self.minimum, -1),
+            Point::new(1, self.maximum, 1),
+        )
+    }
+
+    fn surface_area(&self) -> Option<f64> {
+        let height = self.maximum - self.minimum;
+        if !height.is_finite() {
+            return None;
+        }
+        let caps = if self.closed { 2.0 * PI } else { 0.0 };
+        Some(2.0 * PI * height + caps)
+    }
+
+    fn sample_surface(&self, u: f64, v: f64) -> (Point<spaces::Object>, Vector<spaces::Object>) {
+        let height = self.maximum - self.minimum;
+        // the fraction of the area which is on the side, rather than the caps
+        let side = if self.closed {
+            height / (height + 1.0)
+        } else {
+            1.0
+        };
+        let (sin, cos) = (2.0 * PI * v).sin_cos();
+        if u < side {
+            let p = Point::new(cos, self.minimum + height * u / side, sin);
+            return (p, Vector::new(cos, 0, sin));
+        }
+        // choose a cap, and reuse the rest of u for the radius
+        let u = (u - side) / (1.0 - side) * 2.0;
+        let (y, normal, u) = if u < 1.0 {
+            (self.minimum, Vector::new(0, -1, 0), u)
+        } else {
+            (self.maximum, Vector::new(0, 1, 0), u - 1.0)
+        };
+        let r = u.sqrt();
+        (Point::new(r * cos, y, r * sin), normal)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::csg::*;
+    use crate::*;
+    use approx::*;
+    use std::f64::consts::PI;
+
+    macro_rules! test_intersect {
+        ($name:ident, $cyl:expr, $origin:expr, $direction:expr, $($t:expr),*) => {
+            #[test]
+            fn $name() {
+                let ray = Ray::new($origin, $direction.normalize());
+                let oi = ObjectIndex::test_value(7);
+                let mut inters = Intersections::default();
+                $cyl.intersect(oi, ray, &mut inters);
+                let mut it = inters.iter();
+                $(
+                    assert_relative_eq!(
+                        it.next().expect("expected intersection").t,
+                        $t,
+                        epsilon = 0.00001
+                    );
+                )*
+                assert!(it.next().is_none());
+            }
+        };
+    }
+
+    macro_rules! test_count {
+        ($name:ident, $cyl:expr, $origin:expr, $direction:expr, $count:expr) => {
+            #[test]
+            fn $name() {
+                let ray = Ray::new($origin, $direction.normalize());
+                let oi = ObjectIndex::test_value(7);
+                let mut inters = Intersections::default();
+                $cyl.intersect(oi, ray, &mut inters);
+                assert_eq!(inters.len(), $count);
+            }
+        };
+    }
+
+    test_intersect!(
+        miss_on_surface,
+        Cylinder::default(),
+        Point::new(1, 0, 0),
+        Vector::new(0, 1, 0),
+    );
+    test_intersect!(
+        miss_inside,
+        Cylinder::default(),
+        Point::new(0, 0, 0),
+        Vector::new(0, 1, 0),
+    );
+    test_intersect!(
+        miss_outside,
+        Cylinder::default(),
+        Point::new(0, 0, -5),
+        Vector::new(1, 1, 1),
+    );
+    test_intersect!(
+        hit_tangent,
+        Cylinder::default(),
+        Point::new(1, 0, -5),
+        Vector::new(0, 0, 1),
+        5.0,
+        5.0
+    );
+    test_intersect!(
+        hit_center,
+        Cylinder::default(),
+        Point::new(0, 0, -5),
+        Vector::new(0, 0, 1),
+        4.0,
+        6.0
+    );
+    test_intersect!(
+        hit_oblique,
+        Cylinder::default(),
+        Point::new(0.5, 0, -5),
+        Vector::new(0.1, 1, 1),
+        6.80798,
+        7.08872
+    );
+
+    fn truncated() -> Cylinder {
+        Cylinder::new(1.0, 2.0, false)
+    }
+
+    test_count!(
+        truncated_from_inside,
+        truncated(),
+        Point::new(0, 1.5, 0),
+        Vector::new(0.1, 1, 0),
+        0
+    );
+    test_count!(
+        truncated_above,
+        truncated(),
+        Point::new(0, 3, -5),
+        Vector::new(0, 0, 1),
+        0
+    );
+    test_count!(
+        truncated_below,
+        truncated(),
+        Point::new(0, 0, -5),
+        Vector::new(0, 0, 1),
+        0
+    );
+    test_count!(
+        truncated_at_max,
+        truncated(),
+        Point::new(0, 2, -5),
+        Vector::new(0, 0, 1),
+        0
+    );
+    test_count!(
+        truncated_at_min,
+        truncated(),
+        Point::new(0, 1, -5),
+        Vector::new(0, 0, 1),
+        0
+    );
+    test_count!(
+        truncated_middle,
+        truncated(),
+        Point::new(0, 1.5, -2),
+        Vector::new(0, 0, 1),
+        2
+    );
+
+    fn closed() -> Cylinder {
+        Cylinder::new(1.0, 2.0, true)
+    }
+
+    test_count!(
+        caps_down_center,
+        closed(),
+        Point::new(0, 3, 0),
+        Vector::new(0, -1, 0),
+        2
+    );
+    test_count!(
+        caps_diagonal_above,
+        closed(),
+        Point::new(0, 3, -2),
+        Vector::new(0, -1, 2),
+        2
+    );
+    test_count!(
+        caps_corner_above,
+        closed(),
+        Point::new(0, 4, -2),
+        Vector::new(0, -1, 1),
+        2
+    );
+    test_count!(
+        caps_diagonal_below,
+        closed(),
+        Point::new(0, 0, -2),
+        Vector::new(0, 1, 2),
+        2
+    );
+    test_count!(
+        caps_corner_below,
+        closed(),
+        Point::new(0, -1, -2),
+        Vector::new(0, 1, 1),
+        2
+    );
+
+    macro_rules! test_norm {
+        ($name:ident, $cyl:expr, $point:expr, $normal:expr) => {
+            #[test]
+            fn $name() {
+                let normal = $cyl.normal($point);
+                assert_relative_eq!(normal, $normal);
+            }
+        };
+    }
+
+    test_norm!(
+        norm_pos_x,
+        Cylinder::default(),
+        Point::new(1, 0, 0),
+        Vector::new(1, 0, 0)
+    );
+    test_norm!(
+        norm_neg_z,
+        Cylinder::default(),
+        Point::new(0, 5, -1),
+        Vector::new(0, 0, -1)
+    );
+    test_norm!(
+        norm_pos_z,
+        Cylinder::default(),
+        Point::new(0, -2, 1),
+        Vector::new(0, 0, 1)
+    );
+    test_norm!(
+        norm_neg_x,
+        Cylinder::default(),
+        Point::new(-1, 1, 0),
+        Vector::new(-1, 0, 0)
+    );
+    test_norm!(
+        norm_bottom_center,
+        closed(),
+        Point::new(0, 1, 0),
+        Vector::new(0, -1, 0)
+    );
+    test_norm!(
+        norm_bottom_off_center,
+        closed(),
+        Point::new(0.5, 1, 0),
+        Vector::new(0, -1, 0)
+    );
+    test_norm!(
+        norm_top_center,
+        closed(),
+        Point::new(0, 2, 0),
+        Vector::new(0, 1, 0)
+    );
+    test_norm!(
+        norm_top_off_center,
+        closed(),
+        Point::new(0, 2, 0.5),
+        Vector::new(0, 1, 0)
+    );
+
+    #[test]
+    fn bounds() {
+        let b = closed().bounds();
+        assert_relative_eq!(b.min, Point::new(-1, 1, -1));
+        assert_relative_eq!(b.max, Point::new(1, 2, 1));
+        assert!(!Cylinder::default().bounds().is_finite());
+    }
+
+    #[test]
+    fn sample_surface() {
+        assert!(Cylinder::default().surface_area().is_none());
+        assert_relative_eq!(
+            Cylinder::new(1.0, 2.0, false).surface_area().unwrap(),
+            2.0 * PI
+        );
+        let cyl = closed();
+        assert_relative_eq!(cyl.surface_area().unwrap(), 4.0 * PI);
+        let mut caps = 0;
+        for i in 0..100 {
+            let (p, n) = cyl.sample_surface((i as f64 + 0.5) / 100.0, i as f64 / 37.0 % 1.0);
+            if n.y == 0.0 {
+                assert_relative_eq!(p.x * p.x + p.z * p.z, 1.0);
+                assert!((1.0..2.0).contains(&p.y));
+                assert_relative_eq!(n, Vector::new(p.x, 0, p.z));
+            } else {
+                assert!(p.x * p.x + p.z * p.z <= 1.0);
+                assert_relative_eq!(p.y, if n.y > 0.0 { 2.0 } else { 1.0 });
+                caps += 1;
+            }
+        }
+        // the caps have half the area
+        assert_eq!(caps, 50);
+    }
+}
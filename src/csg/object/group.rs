@@ -0,0 +1,129 @@
+use crate::csg::Object;
+
+/// A group of objects, transformed together.
+///
+/// Use `Object::group` to make a group into an object which can be transformed, given a
+/// material, added to a world, or added to another group.
+#[derive(Debug, Default)]
+pub struct Group {
+    pub(crate) children: Vec<Object>,
+}
+
+impl Group {
+    /// Create a new, empty group.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return an updated group with the given child added.
+    pub fn with_child(mut self, child: Object) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Add a child to this group.
+    pub fn add_child(&mut self, child: Object) {
+        self.children.push(child);
+    }
+
+    /// Get the number of children in this group.
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    /// Determine whether this group has no children.
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::csg::*;
+    use crate::*;
+    use approx::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn intersect_empty_group() {
+        let mut w = World::default();
+        assert_eq!(w.add_group(Object::group(Group::new())), None);
+        let r = Ray::new(Point::new(0, 0, 0), Vector::new(0, 0, 1));
+        let mut inters = Intersections::default();
+        w.intersect(&r, &mut inters);
+        assert!(inters.is_empty());
+    }
+
+    #[test]
+    fn intersect_group() {
+        let mut w = World::default();
+        let s1 = w.add_object(Object::group(
+            Group::new()
+                .with_child(Object::new(Sphere))
+                .with_child(Object::new(Sphere).with_transform(Mat::identity().translate(0, 0, -3)))
+                .with_child(Object::new(Sphere).with_transform(Mat::identity().translate(5, 0, 0))),
+        ));
+        let s2 = ObjectIndex::test_value(1);
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+        let mut inters = Intersections::default();
+        w.intersect(&r, &mut inters);
+        let hits: Vec<_> = inters.iter().map(|i| i.object_index).collect();
+        assert_eq!(hits, vec![s2, s2, s1, s1]);
+    }
+
+    #[test]
+    fn intersect_transformed_group() {
+        let mut w = World::default();
+        w.add_object(
+            Object::group(Group::new().with_child(
+                Object::new(Sphere).with_transform(Mat::identity().translate(5, 0, 0)),
+            ))
+            .with_transform(Mat::identity().scale(2, 2, 2)),
+        );
+        let r = Ray::new(Point::new(10, 0, -10), Vector::new(0, 0, 1));
+        let mut inters = Intersections::default();
+        w.intersect(&r, &mut inters);
+        assert_eq!(inters.len(), 2);
+    }
+
+    #[test]
+    fn normal_in_nested_groups() {
+        let mut w = World::default();
+        let s = w.add_object(
+            Object::group(
+                Group::new().with_child(
+                    Object::group(Group::new().with_child(
+                        Object::new(Sphere).with_transform(Mat::identity().translate(5, 0, 0)),
+                    ))
+                    .with_transform(Mat::identity().scale(1, 2, 3)),
+                ),
+            )
+            .with_transform(Mat::identity().rotate_y(PI / 2.0)),
+        );
+        let n = w[s].normal(Point::new(1.7321, 1.1547, -5.5774));
+        assert!(Relative {
+            epsilon: 0.0001,
+            max_relative: 0.0001,
+        }
+        .eq(&n, &Vector::new(0.2857, 0.4286, -0.8571)));
+    }
+
+    #[test]
+    fn material_inherited() {
+        let mut w = World::default();
+        let s1 = w.add_object(
+            Object::group(
+                Group::new()
+                    .with_child(Object::new(Sphere))
+                    .with_child(Object::new(Cube).with_material(Material::default()))
+                    .with_child(Object::group(Group::new().with_child(Object::new(Sphere)))),
+            )
+            .with_material(Material::default().with_ambient(0.5)),
+        );
+        assert_eq!(s1, ObjectIndex::test_value(0));
+        let ambient = |i| w[ObjectIndex::test_value(i)].material().ambient;
+        assert_relative_eq!(ambient(0), 0.5);
+        assert_relative_eq!(ambient(1), 0.1);
+        assert_relative_eq!(ambient(2), 0.5);
+    }
+}
@@ -1,9 +1,22 @@
+mod cone;
+mod csg;
 mod cube;
+mod cylinder;
+mod group;
+#[allow(clippy::module_inception)]
 mod object;
 mod plane;
+mod smooth_triangle;
 mod sphere;
+mod triangle;
 
+pub use cone::*;
+pub use csg::*;
 pub use cube::*;
+pub use cylinder::*;
+pub use group::*;
 pub use object::*;
 pub use plane::*;
+pub use smooth_triangle::*;
 pub use sphere::*;
+pub use triangle::*;
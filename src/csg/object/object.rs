@@ -1,4 +1,9 @@
-use crate::{spaces, Color, Intersections, Mat, Material, ObjectIndex, Point, Ray, Vector, World};
+use crate::csg::{Csg, CsgOp, Group};
+use crate::{
+    spaces, Bounds, Color, Intersection, Intersections, Mat, Material, ObjectIndex, Point, Ray,
+    Vector, World,
+};
+use std::sync::OnceLock;
 
 /// ObjectInnner defines methods to handle the particularities of an object, in object space.
 pub trait ObjectInner: std::fmt::Debug + Sync + Send {
@@ -12,34 +17,173 @@ pub trait ObjectInner: std::fmt::Debug + Sync + Send {
 
     /// Normal calculates the normal of the given point on the surface of this object.
     fn normal(&self, point: Point<spaces::Object>) -> Vector<spaces::Object>;
+
+    /// Calculate the normal at the given intersection with this object, at the given point.
+    /// This is useful for objects which record (u, v) coordinates in their intersections.
+    fn normal_at(
+        &self,
+        point: Point<spaces::Object>,
+        _hit: &Intersection,
+    ) -> Vector<spaces::Object> {
+        self.normal(point)
+    }
+
+    /// Bounds calculates the axis-aligned bounds of this object.  Unbounded objects should
+    /// return `Bounds::infinite()`.
+    fn bounds(&self) -> Bounds<spaces::Object>;
+
+    /// Calculate the surface area of this object, if it is finite and points on the surface
+    /// can be chosen with `sample_surface`.
+    fn surface_area(&self) -> Option<f64> {
+        None
+    }
+
+    /// Choose a point on the surface of this object from two numbers in [0, 1), returning it
+    /// and the normal there.  Uniformly distributed numbers must give points uniformly
+    /// distributed over the surface.  This is only called for objects with a surface area.
+    fn sample_surface(&self, _u: f64, _v: f64) -> (Point<spaces::Object>, Vector<spaces::Object>) {
+        unreachable!("only objects with a surface area can be sampled")
+    }
 }
 
 #[derive(Debug)]
 pub struct Object {
-    /// The implementation of this object, in object space
-    inner: Box<dyn ObjectInner>,
+    /// The shape of this object, in object space
+    shape: Shape,
 
     /// The transformation from world to object space.
+    ///
+    /// For an object within a group, "world space" is the group's object space until the
+    /// object is added to a world.
     transform: Mat<4, spaces::World, spaces::Object>,
 
     /// The transformation of object-space normals to world-space normals.
     transp_transform: Mat<4, spaces::Object, spaces::World>,
 
-    /// The material comprising this object.
-    pub(crate) material: Material,
+    /// The material comprising this object, if not inherited from a containing group.
+    material: Option<Material>,
+}
+
+#[derive(Debug)]
+enum Shape {
+    /// A primitive object
+    Primitive(Box<dyn ObjectInner>),
+
+    /// A group of objects, which is flattened into its primitives when added to a world
+    Group(Vec<Object>),
+
+    /// A CSG combination of two objects, which is flattened into its primitives when added to
+    /// a world
+    Csg(Box<Csg>),
+}
+
+/// A unit of intersection in a world, referring to the world's flattened primitives.
+#[derive(Debug)]
+pub(crate) enum Node {
+    /// A single primitive object
+    Primitive(ObjectIndex),
+
+    /// A CSG combination.  All primitives in `left` have indexes less than `split`, and all
+    /// primitives in `right` have indexes greater than or equal to `split`.
+    Csg {
+        op: CsgOp,
+        left: Vec<Node>,
+        right: Vec<Node>,
+        split: usize,
+        /// The index of the first primitive, which stands for the whole combination when
+        /// tracking the objects containing a ray
+        first: ObjectIndex,
+    },
+}
+
+impl Node {
+    /// Calculate the intersections of the given ray with this node.
+    pub(crate) fn intersect(
+        &self,
+        objects: &[Object],
+        ray: &Ray<spaces::World>,
+        inters: &mut Intersections,
+    ) {
+        match self {
+            Node::Primitive(idx) => objects[idx.0].intersect(*idx, ray, inters),
+            Node::Csg {
+                op,
+                left,
+                right,
+                split,
+                first,
+            } => {
+                let mut child_inters = Intersections::default();
+                for node in left.iter().chain(right.iter()) {
+                    node.intersect(objects, ray, &mut child_inters);
+                }
+
+                // keep only the intersections on the surface of the combined object
+                let mut in_left = false;
+                let mut in_right = false;
+                for i in child_inters.iter() {
+                    let left_hit = i.object_index.0 < *split;
+                    if op.allowed(left_hit, in_left, in_right) {
+                        inters.push(Intersection {
+                            container: *first,
+                            ..*i
+                        });
+                    }
+                    if left_hit {
+                        in_left = !in_left;
+                    } else {
+                        in_right = !in_right;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Calculate the world-space bounds of this node.
+    pub(crate) fn bounds(&self, objects: &[Object]) -> Bounds<spaces::World> {
+        match self {
+            Node::Primitive(idx) => objects[idx.0].bounds(),
+            Node::Csg { left, right, .. } => left
+                .iter()
+                .chain(right.iter())
+                .fold(Bounds::empty(), |acc, n| acc.union(&n.bounds(objects))),
+        }
+    }
 }
 
+/// The material used for objects which have none of their own.
+static DEFAULT_MATERIAL: OnceLock<Material> = OnceLock::new();
+
 impl Object {
     /// Create a new object.
     ///
     /// Use the `with_...` methods to adjust the transform and material, in
     /// a builder pattern.
     pub fn new(inner: impl ObjectInner + 'static) -> Self {
+        Self::with_shape(Shape::Primitive(Box::new(inner)))
+    }
+
+    /// Create a new object from a group of objects.
+    ///
+    /// The group's transform applies to all of its children, on top of their own transforms.
+    /// The group's material, if set, applies to any children without a material of their own.
+    pub fn group(group: Group) -> Self {
+        Self::with_shape(Shape::Group(group.children))
+    }
+
+    /// Create a new object from a CSG combination of objects.
+    ///
+    /// The transform and material behave as for a group.
+    pub fn csg(csg: Csg) -> Self {
+        Self::with_shape(Shape::Csg(Box::new(csg)))
+    }
+
+    fn with_shape(shape: Shape) -> Self {
         Self {
-            inner: Box::new(inner),
+            shape,
             transform: Mat::identity(),
             transp_transform: Mat::identity(),
-            material: Material::default(),
+            material: None,
         }
     }
 
@@ -52,10 +196,86 @@ impl Object {
 
     /// Return an updated object with the given material.
     pub fn with_material(mut self, material: Material) -> Self {
-        self.material = material;
+        self.material = Some(material);
         self
     }
 
+    /// Get the material comprising this object.
+    pub(crate) fn material(&self) -> &Material {
+        self.material
+            .as_ref()
+            .unwrap_or_else(|| DEFAULT_MATERIAL.get_or_init(Material::default))
+    }
+
+    /// Get the implementation of this primitive object.
+    fn inner(&self) -> &dyn ObjectInner {
+        match self.shape {
+            Shape::Primitive(ref inner) => inner.as_ref(),
+            Shape::Group(_) | Shape::Csg(_) => {
+                unreachable!("groups and CSG objects are flattened when added to a world")
+            }
+        }
+    }
+
+    /// Flatten this object into primitives, appending them to `objects` and the nodes by which
+    /// they are intersected to `nodes`.  Each primitive's transform is composed with those of
+    /// its containing groups, and inherits the nearest group material if it has none of its
+    /// own.
+    ///
+    /// The `parent` transform is from world space to the space containing this object.
+    pub(crate) fn flatten_into(
+        self,
+        parent: Mat<4, spaces::World, spaces::World>,
+        parent_material: Option<&Material>,
+        objects: &mut Vec<Object>,
+        nodes: &mut Vec<Node>,
+    ) {
+        let transform = self.transform * parent;
+        let material = self.material.or_else(|| parent_material.cloned());
+        match self.shape {
+            Shape::Primitive(_) => {
+                nodes.push(Node::Primitive(ObjectIndex(objects.len())));
+                objects.push(Object {
+                    shape: self.shape,
+                    transform,
+                    transp_transform: transform.transpose(),
+                    material,
+                });
+            }
+            Shape::Group(children) => {
+                for child in children {
+                    child.flatten_into(transform.as_spaces(), material.as_ref(), objects, nodes);
+                }
+            }
+            Shape::Csg(csg) => {
+                let Csg { op, left, right } = *csg;
+                let first = ObjectIndex(objects.len());
+                let mut left_nodes = vec![];
+                let mut right_nodes = vec![];
+                left.flatten_into(
+                    transform.as_spaces(),
+                    material.as_ref(),
+                    objects,
+                    &mut left_nodes,
+                );
+                let split = objects.len();
+                right.flatten_into(
+                    transform.as_spaces(),
+                    material.as_ref(),
+                    objects,
+                    &mut right_nodes,
+                );
+                nodes.push(Node::Csg {
+                    op,
+                    left: left_nodes,
+                    right: right_nodes,
+                    split,
+                    first,
+                });
+            }
+        }
+    }
+
     /// Calculate the intersections of the given ray with this object.
     pub(crate) fn intersect(
         &self,
@@ -64,55 +284,114 @@ impl Object {
         inters: &mut Intersections,
     ) {
         let obj_ray = self.transform * *ray;
-        self.inner.intersect(object_index, obj_ray, inters);
+        self.inner().intersect(object_index, obj_ray, inters);
+    }
+
+    /// Calculate the world-space bounds of this object.
+    pub(crate) fn bounds(&self) -> Bounds<spaces::World> {
+        self.inner().bounds().transform(self.transform.inverse())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn color_at(
         &self,
         world: &World,
         from_obj: Option<&Object>,
-        t: f64,
+        to_obj: Option<&Object>,
+        hit: &Intersection,
         ray: &Ray<spaces::World>,
         total_contribution: f64,
         debug: bool,
     ) -> Color {
-        // the point at which the intersection occurred
-        let point = ray.position(t);
+        let (point, obj_point, normalv) = self.surface_at(hit, ray);
 
         // vector from point to the eye
         let eyev = -ray.direction;
 
+        self.material().color_at(
+            world,
+            from_obj.map(|o| o.material()),
+            to_obj.map(|o| o.material()),
+            ray,
+            point,
+            obj_point,
+            eyev,
+            normalv,
+            total_contribution,
+            debug,
+        )
+    }
+
+    /// Get the point of the given intersection of a ray with this object, in world and object
+    /// space, and the normal there in world space, facing the origin of the ray.
+    pub(crate) fn surface_at(
+        &self,
+        hit: &Intersection,
+        ray: &Ray<spaces::World>,
+    ) -> (
+        Point<spaces::World>,
+        Point<spaces::Object>,
+        Vector<spaces::World>,
+    ) {
+        // the point at which the intersection occurred
+        let point = ray.position(hit.t);
+
         // point in object space
         let obj_point = self.transform * point;
 
-        // normal in object space
-        let obj_normal = self.inner.normal(obj_point);
+        // normal in object space, perturbed by the material
+        let obj_normal = self.inner().normal_at(obj_point, hit);
+        let obj_normal = self.material().normal_at(obj_point, obj_normal);
 
         // normal in world space
         let mut normalv = (self.transp_transform * obj_normal).normalize();
-        if normalv.dot(eyev) < 0.0 {
+        if normalv.dot(ray.direction) > 0.0 {
             // use the inside surface, with the opposite normal
             normalv = -normalv;
         }
 
-        self.material.color_at(
-            world,
-            from_obj.map(|o| &o.material),
-            ray,
-            point,
-            obj_point,
-            eyev,
-            normalv,
-            total_contribution,
-            debug,
-        )
+        (point, obj_point, normalv)
+    }
+
+    /// Estimate the surface area of this object in world space, if it is finite and can be
+    /// sampled.  The estimate is exact for transforms which scale uniformly.
+    pub(crate) fn surface_area(&self) -> Option<f64> {
+        let area = self.inner().surface_area()?;
+        // areas scale as the 2/3 power of volumes
+        Some(area * (1.0 / self.transform.determinant().abs()).powf(2.0 / 3.0))
+    }
+
+    /// Choose a point on the surface of this object from two numbers in [0, 1), returning the
+    /// point and the normal there in world space, and the probability density of choosing it
+    /// per unit of world-space area when the numbers are uniformly distributed.  Returns None
+    /// for objects which cannot be sampled.
+    pub(crate) fn sample_surface(
+        &self,
+        u: f64,
+        v: f64,
+    ) -> Option<(Point<spaces::World>, Vector<spaces::World>, f64)> {
+        let area = self.inner().surface_area()?;
+        let (obj_point, obj_normal) = self.inner().sample_surface(u, v);
+        let normal = self.transp_transform * obj_normal.normalize();
+        // the factor by which the transform stretches areas around the point
+        let stretch = normal.magnitude() / self.transform.determinant().abs();
+        Some((
+            self.transform.inverse() * obj_point,
+            normal.normalize(),
+            1.0 / (area * stretch),
+        ))
+    }
+
+    /// Get the color of the light passing through this object at the given point.
+    pub(crate) fn transmission(&self, point: Point<spaces::World>) -> Color {
+        self.material().transmission(self.transform * point)
     }
 
     /// Get only the normal (used for testing objects)
     #[cfg(test)]
     pub fn normal(&self, point: Point<spaces::World>) -> Vector<spaces::World> {
         let obj_point = self.transform * point;
-        let obj_normal = self.inner.normal(obj_point);
+        let obj_normal = self.inner().normal(obj_point);
         let world_normal = self.transp_transform * obj_normal;
         world_normal.normalize()
     }
@@ -163,12 +442,27 @@ mod test {
         let mut inters = Intersections::default();
         o.intersect(ObjectIndex::test_value(0), &r, &mut inters);
         // ray of length 2 hits sphere at 0, 0, 9, so t=4.5
-        let (from_obj, t, to_obj) = inters.hit();
+        let (from_obj, hit, to_obj) = inters.hit();
         assert!(from_obj.is_none());
-        assert_relative_eq!(t.unwrap(), 4.5);
+        assert_relative_eq!(hit.unwrap().t, 4.5);
         assert_eq!(to_obj, Some(ObjectIndex::test_value(0)));
     }
 
+    #[test]
+    fn bounds() {
+        let o =
+            Object::new(Sphere).with_transform(Mat::identity().scale(1, 2, 3).translate(5, 0, 0));
+        let b = o.bounds();
+        assert_relative_eq!(b.min, Point::new(4, -2, -3));
+        assert_relative_eq!(b.max, Point::new(6, 2, 3));
+    }
+
+    #[test]
+    fn bounds_unbounded() {
+        let o = Object::new(Plane).with_transform(Mat::identity().translate(0, 2, 0));
+        assert!(!o.bounds().is_finite());
+    }
+
     #[test]
     fn normal() {
         // a sphere stretched 2x vertically
@@ -178,12 +472,33 @@ mod test {
         // ray hits halfway up the sphere.
         let mut inters = Intersections::default();
         o.intersect(ObjectIndex::test_value(0), &r, &mut inters);
-        let (_, t, to_obj) = inters.hit();
-        let p = r.position(t.unwrap());
+        let (_, hit, to_obj) = inters.hit();
+        let p = r.position(hit.unwrap().t);
         assert_eq!(to_obj, Some(ObjectIndex::test_value(0)));
 
         let n = o.normal(p);
 
         assert_relative_eq!(n.magnitude(), 1.0);
     }
+
+    #[test]
+    fn sample_surface() {
+        assert!(Object::new(Plane).surface_area().is_none());
+        assert!(Object::new(Plane).sample_surface(0.5, 0.5).is_none());
+
+        let o =
+            Object::new(Sphere).with_transform(Mat::identity().scale(2, 2, 2).translate(0, 5, 0));
+        assert_relative_eq!(o.surface_area().unwrap(), 16.0 * std::f64::consts::PI);
+        let (p, n, density) = o.sample_surface(0.3, 0.6).unwrap();
+        assert_relative_eq!((p - Point::new(0, 5, 0)).magnitude(), 2.0);
+        assert_relative_eq!(n, (p - Point::new(0, 5, 0)).normalize());
+        assert_relative_eq!(density, 1.0 / (16.0 * std::f64::consts::PI));
+
+        // the face at x = 1 is stretched six times, to 4 by 6
+        let o = Object::new(Cube).with_transform(Mat::identity().scale(1, 2, 3));
+        let (p, n, density) = o.sample_surface(0.1, 0.5).unwrap();
+        assert_relative_eq!(p.x, 1.0);
+        assert_relative_eq!(n, Vector::new(1, 0, 0));
+        assert_relative_eq!(density, 1.0 / (24.0 * 6.0));
+    }
 }
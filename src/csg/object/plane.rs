@@ -1,5 +1,5 @@
 use crate::csg::ObjectInner;
-use crate::{spaces, Intersections, ObjectIndex, Point, Ray, Vector};
+use crate::{spaces, Bounds, Intersections, ObjectIndex, Point, Ray, Vector};
 
 /// A plane in x-z
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -22,6 +22,13 @@ impl ObjectInner for Plane {
     fn normal(&self, _point: Point<spaces::Object>) -> Vector<spaces::Object> {
         Vector::new(0, 1, 0)
     }
+
+    fn bounds(&self) -> Bounds<spaces::Object> {
+        Bounds::new(
+            Point::new(f64::NEG_INFINITY, 0, f64::NEG_INFINITY),
+            Point::new(f64::INFINITY, 0, f64::INFINITY),
+        )
+    }
 }
 
 #[cfg(test)]
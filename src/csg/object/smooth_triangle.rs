@@ -0,0 +1,160 @@
+use crate::csg::{ObjectInner, Triangle};
+use crate::{spaces, Bounds, Intersection, Intersections, ObjectIndex, Point, Ray, Vector};
+
+/// A triangle with a normal given at each vertex.  The normal at any point on the triangle
+/// is interpolated from the vertex normals, giving the appearance of a smooth surface when
+/// triangles share vertex normals.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SmoothTriangle {
+    triangle: Triangle,
+    n1: Vector<spaces::Object>,
+    n2: Vector<spaces::Object>,
+    n3: Vector<spaces::Object>,
+}
+
+impl SmoothTriangle {
+    pub fn new(
+        p1: Point<spaces::Object>,
+        p2: Point<spaces::Object>,
+        p3: Point<spaces::Object>,
+        n1: Vector<spaces::Object>,
+        n2: Vector<spaces::Object>,
+        n3: Vector<spaces::Object>,
+    ) -> Self {
+        Self {
+            triangle: Triangle::new(p1, p2, p3),
+            n1,
+            n2,
+            n3,
+        }
+    }
+
+    /// Return an updated triangle with the given texture coordinates at p1, p2 and p3.  See
+    /// `Triangle::with_texture_coords`.
+    pub fn with_texture_coords(
+        mut self,
+        uv1: (f64, f64),
+        uv2: (f64, f64),
+        uv3: (f64, f64),
+    ) -> Self {
+        self.triangle = self.triangle.with_texture_coords(uv1, uv2, uv3);
+        self
+    }
+}
+
+impl ObjectInner for SmoothTriangle {
+    fn intersect(
+        &self,
+        object_index: ObjectIndex,
+        ray: Ray<spaces::Object>,
+        inters: &mut Intersections,
+    ) {
+        self.triangle.intersect(object_index, ray, inters);
+    }
+
+    /// Without (u, v) coordinates, the normal is that of the flat triangle.
+    fn normal(&self, point: Point<spaces::Object>) -> Vector<spaces::Object> {
+        self.triangle.normal(point)
+    }
+
+    fn normal_at(
+        &self,
+        point: Point<spaces::Object>,
+        hit: &Intersection,
+    ) -> Vector<spaces::Object> {
+        match self.triangle.barycentric(point, hit) {
+            Some((u, v)) => self.n2 * u + self.n3 * v + self.n1 * (1.0 - u - v),
+            None => self.normal(point),
+        }
+    }
+
+    fn bounds(&self) -> Bounds<spaces::Object> {
+        self.triangle.bounds()
+    }
+
+    fn surface_area(&self) -> Option<f64> {
+        self.triangle.surface_area()
+    }
+
+    fn sample_surface(&self, u: f64, v: f64) -> (Point<spaces::Object>, Vector<spaces::Object>) {
+        self.triangle.sample_surface(u, v)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::csg::*;
+    use crate::*;
+    use approx::*;
+
+    fn tri() -> SmoothTriangle {
+        SmoothTriangle::new(
+            Point::new(0, 1, 0),
+            Point::new(-1, 0, 0),
+            Point::new(1, 0, 0),
+            Vector::new(0, 1, 0),
+            Vector::new(-1, 0, 0),
+            Vector::new(1, 0, 0),
+        )
+    }
+
+    #[test]
+    fn intersection_stores_uv() {
+        let ray = Ray::new(Point::new(-0.2, 0.3, -2), Vector::new(0, 0, 1));
+        let mut inters = Intersections::default();
+        tri().intersect(ObjectIndex::test_value(1), ray, &mut inters);
+        let (u, v) = inters.iter().next().expect("intersection").uv.unwrap();
+        assert_relative_eq!(u, 0.45, epsilon = 0.00001);
+        assert_relative_eq!(v, 0.25, epsilon = 0.00001);
+    }
+
+    #[test]
+    fn interpolated_normal() {
+        let hit = Intersection {
+            t: 1.0,
+            object_index: ObjectIndex::test_value(1),
+            uv: Some((0.45, 0.25)),
+            container: ObjectIndex::test_value(1),
+        };
+        let n = tri().normal_at(Point::new(0, 0, 0), &hit);
+        assert_relative_eq!(
+            n.normalize(),
+            Vector::new(-0.5547, 0.83205, 0),
+            epsilon = 0.00001
+        );
+    }
+
+    #[test]
+    fn interpolated_normal_with_texture_coords() {
+        let t = tri().with_texture_coords((0.5, 1.0), (0.0, 0.0), (1.0, 0.0));
+        let ray = Ray::new(Point::new(-0.2, 0.3, -2), Vector::new(0, 0, 1));
+        let mut inters = Intersections::default();
+        t.intersect(ObjectIndex::test_value(1), ray, &mut inters);
+        let hit = inters.iter().next().expect("intersection");
+        let n = t.normal_at(Point::new(-0.2, 0.3, 0), hit);
+        assert_relative_eq!(
+            n.normalize(),
+            Vector::new(-0.5547, 0.83205, 0),
+            epsilon = 0.00001
+        );
+    }
+
+    #[test]
+    fn shading_uses_interpolated_normal() {
+        // lit head-on, the flat triangle is brighter than the smooth one, whose interpolated
+        // normal points away from the light
+        let shade = |obj: Object| {
+            let mut w = World::new(Light::new_point(Point::new(0, 0, -10), Color::white()));
+            w.add_object(obj);
+            let ray = Ray::new(Point::new(-0.2, 0.3, -2), Vector::new(0, 0, 1));
+            w.color_at(&ray, 1.0, false)
+        };
+        let flat = shade(Object::new(Triangle::new(
+            Point::new(0, 1, 0),
+            Point::new(-1, 0, 0),
+            Point::new(1, 0, 0),
+        )));
+        let smooth = shade(Object::new(tri()));
+        assert!(smooth.red < flat.red);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::csg::ObjectInner;
-use crate::{spaces, Intersections, ObjectIndex, Point, Ray, Vector};
+use crate::{spaces, Bounds, Intersections, ObjectIndex, Point, Ray, Vector};
+use std::f64::consts::PI;
 
 /// A unit sphere centered at the origin
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -28,6 +29,23 @@ impl ObjectInner for Sphere {
     fn normal(&self, point: Point<spaces::Object>) -> Vector<spaces::Object> {
         point.as_vector()
     }
+
+    fn bounds(&self) -> Bounds<spaces::Object> {
+        Bounds::new(Point::new(-1, -1, -1), Point::new(1, 1, 1))
+    }
+
+    fn surface_area(&self) -> Option<f64> {
+        Some(4.0 * PI)
+    }
+
+    fn sample_surface(&self, u: f64, v: f64) -> (Point<spaces::Object>, Vector<spaces::Object>) {
+        // a uniform height gives a uniform area, as Archimedes found
+        let y = 1.0 - 2.0 * u;
+        let r = (1.0 - y * y).max(0.0).sqrt();
+        let phi = 2.0 * PI * v;
+        let p = Point::new(r * phi.cos(), y, r * phi.sin());
+        (p, p.as_vector())
+    }
 }
 
 #[cfg(test)]
@@ -159,4 +177,19 @@ mod test {
             Vector::new(0, 0.9701425001453319, -0.24253562503633302)
         );
     }
+
+    #[test]
+    fn sample_surface() {
+        assert_relative_eq!(Sphere.surface_area().unwrap(), 4.0 * PI);
+        let mut mean_y = 0.0;
+        for i in 0..10 {
+            for j in 0..10 {
+                let (p, n) = Sphere.sample_surface((i as f64 + 0.5) / 10.0, j as f64 / 10.0);
+                assert_relative_eq!(p.as_vector().magnitude(), 1.0);
+                assert_relative_eq!(n, Sphere.normal(p));
+                mean_y += p.y / 100.0;
+            }
+        }
+        assert_relative_eq!(mean_y, 0.0, epsilon = 1e-12);
+    }
 }
@@ -0,0 +1,261 @@
+use crate::csg::ObjectInner;
+use crate::{spaces, Bounds, Intersection, Intersections, ObjectIndex, Point, Ray, Vector};
+
+const EPSILON: f64 = 0.00001;
+
+/// A flat triangle with the given vertices.
+///
+/// Intersections with a triangle carry the barycentric (u, v) coordinates of the intersection,
+/// such that the point is `p1 + u * (p2 - p1) + v * (p3 - p1)`.  A triangle with texture
+/// coordinates instead carries those coordinates, interpolated across the triangle.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Triangle {
+    p1: Point<spaces::Object>,
+    p2: Point<spaces::Object>,
+    p3: Point<spaces::Object>,
+
+    /// Edge from p1 to p2
+    e1: Vector<spaces::Object>,
+
+    /// Edge from p1 to p3
+    e2: Vector<spaces::Object>,
+
+    /// Normal, constant over the surface of the triangle
+    normal: Vector<spaces::Object>,
+
+    /// Texture coordinates at p1, p2 and p3
+    texture_coords: Option<[(f64, f64); 3]>,
+}
+
+impl Triangle {
+    pub fn new(
+        p1: Point<spaces::Object>,
+        p2: Point<spaces::Object>,
+        p3: Point<spaces::Object>,
+    ) -> Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        Self {
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal: e2.cross(e1).normalize(),
+            texture_coords: None,
+        }
+    }
+
+    /// Return an updated triangle with the given texture coordinates at p1, p2 and p3.
+    pub fn with_texture_coords(
+        mut self,
+        uv1: (f64, f64),
+        uv2: (f64, f64),
+        uv3: (f64, f64),
+    ) -> Self {
+        self.texture_coords = Some([uv1, uv2, uv3]);
+        self
+    }
+
+    /// Calculate the (u, v) coordinates to report for the point with the given barycentric
+    /// coordinates.
+    pub(crate) fn surface_uv(&self, u: f64, v: f64) -> (f64, f64) {
+        match self.texture_coords {
+            Some([(u1, v1), (u2, v2), (u3, v3)]) => (
+                u1 + u * (u2 - u1) + v * (u3 - u1),
+                v1 + u * (v2 - v1) + v * (v3 - v1),
+            ),
+            None => (u, v),
+        }
+    }
+
+    /// Get the barycentric coordinates of a hit at the given point on this triangle.  These are
+    /// the hit's (u, v) coordinates, unless they are texture coordinates, in which case they are
+    /// calculated from the point.
+    pub(crate) fn barycentric(
+        &self,
+        point: Point<spaces::Object>,
+        hit: &Intersection,
+    ) -> Option<(f64, f64)> {
+        if self.texture_coords.is_none() {
+            return hit.uv;
+        }
+        let p = point - self.p1;
+        let (d11, d12, d22) = (
+            self.e1.dot(self.e1),
+            self.e1.dot(self.e2),
+            self.e2.dot(self.e2),
+        );
+        let (dp1, dp2) = (p.dot(self.e1), p.dot(self.e2));
+        let denom = d11 * d22 - d12 * d12;
+        Some((
+            (d22 * dp1 - d12 * dp2) / denom,
+            (d11 * dp2 - d12 * dp1) / denom,
+        ))
+    }
+
+    /// Calculate the intersection of the given ray with this triangle, using the Möller–Trumbore
+    /// algorithm, returning (t, u, v).
+    pub(crate) fn intersect_uv(&self, ray: Ray<spaces::Object>) -> Option<(f64, f64, f64)> {
+        let dir_cross_e2 = ray.direction.cross(self.e2);
+        let det = self.e1.dot(dir_cross_e2);
+        if det.abs() < EPSILON {
+            // the ray is parallel to the triangle
+            return None;
+        }
+
+        let f = 1.0 / det;
+        let p1_to_origin = ray.origin - self.p1;
+        let u = f * p1_to_origin.dot(dir_cross_e2);
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let origin_cross_e1 = p1_to_origin.cross(self.e1);
+        let v = f * ray.direction.dot(origin_cross_e1);
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = f * self.e2.dot(origin_cross_e1);
+        Some((t, u, v))
+    }
+}
+
+impl ObjectInner for Triangle {
+    fn intersect(
+        &self,
+        object_index: ObjectIndex,
+        ray: Ray<spaces::Object>,
+        inters: &mut Intersections,
+    ) {
+        if let Some((t, u, v)) = self.intersect_uv(ray) {
+            let (u, v) = self.surface_uv(u, v);
+            inters.add_with_uv(t, object_index, u, v);
+        }
+    }
+
+    fn normal(&self, _point: Point<spaces::Object>) -> Vector<spaces::Object> {
+        self.normal
+    }
+
+    fn bounds(&self) -> Bounds<spaces::Object> {
+        Bounds::new(self.p1, self.p1)
+            .with_point(self.p2)
+            .with_point(self.p3)
+    }
+
+    fn surface_area(&self) -> Option<f64> {
+        Some(self.e1.cross(self.e2).magnitude() / 2.0)
+    }
+
+    fn sample_surface(&self, u: f64, v: f64) -> (Point<spaces::Object>, Vector<spaces::Object>) {
+        // fold points beyond the third edge of the parallelogram back into the triangle
+        let (u, v) = if u + v > 1.0 {
+            (1.0 - u, 1.0 - v)
+        } else {
+            (u, v)
+        };
+        (self.p1 + self.e1 * u + self.e2 * v, self.normal)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::csg::*;
+    use crate::*;
+    use approx::*;
+
+    fn tri() -> Triangle {
+        Triangle::new(
+            Point::new(0, 1, 0),
+            Point::new(-1, 0, 0),
+            Point::new(1, 0, 0),
+        )
+    }
+
+    #[test]
+    fn construct() {
+        let t = tri();
+        assert_relative_eq!(t.e1, Vector::new(-1, -1, 0));
+        assert_relative_eq!(t.e2, Vector::new(1, -1, 0));
+        assert_relative_eq!(t.normal, Vector::new(0, 0, -1));
+    }
+
+    #[test]
+    fn normal() {
+        let t = tri();
+        assert_relative_eq!(t.normal(Point::new(0, 0.5, 0)), t.normal);
+        assert_relative_eq!(t.normal(Point::new(-0.5, 0.75, 0)), t.normal);
+        assert_relative_eq!(t.normal(Point::new(0.5, 0.25, 0)), t.normal);
+    }
+
+    macro_rules! test_miss {
+        ($name:ident, $origin:expr, $direction:expr) => {
+            #[test]
+            fn $name() {
+                let ray = Ray::new($origin, $direction);
+                let mut inters = Intersections::default();
+                tri().intersect(ObjectIndex::test_value(1), ray, &mut inters);
+                assert!(inters.is_empty());
+            }
+        };
+    }
+
+    test_miss!(miss_parallel, Point::new(0, -1, -2), Vector::new(0, 1, 0));
+    test_miss!(miss_p1_p3_edge, Point::new(1, 1, -2), Vector::new(0, 0, 1));
+    test_miss!(miss_p1_p2_edge, Point::new(-1, 1, -2), Vector::new(0, 0, 1));
+    test_miss!(miss_p2_p3_edge, Point::new(0, -1, -2), Vector::new(0, 0, 1));
+
+    #[test]
+    fn hit() {
+        let ray = Ray::new(Point::new(0, 0.5, -2), Vector::new(0, 0, 1));
+        let mut inters = Intersections::default();
+        tri().intersect(ObjectIndex::test_value(1), ray, &mut inters);
+        let mut it = inters.iter();
+        let i = it.next().expect("intersection");
+        assert_relative_eq!(i.t, 2.0);
+        let (u, v) = i.uv.expect("uv");
+        assert_relative_eq!(u, 0.25);
+        assert_relative_eq!(v, 0.25);
+        assert!(it.next().is_none());
+    }
+
+    #[test]
+    fn hit_texture_coords() {
+        let t = tri().with_texture_coords((0.5, 1.0), (0.0, 0.0), (1.0, 0.0));
+        let ray = Ray::new(Point::new(-0.2, 0.3, -2), Vector::new(0, 0, 1));
+        let mut inters = Intersections::default();
+        t.intersect(ObjectIndex::test_value(1), ray, &mut inters);
+        let hit = *inters.iter().next().expect("intersection");
+        let (u, v) = hit.uv.expect("uv");
+        assert_relative_eq!(u, 0.4, epsilon = 1e-9);
+        assert_relative_eq!(v, 0.3, epsilon = 1e-9);
+
+        // the barycentric coordinates are recovered from the point
+        let (u, v) = t.barycentric(Point::new(-0.2, 0.3, 0), &hit).unwrap();
+        assert_relative_eq!(u, 0.45, epsilon = 1e-9);
+        assert_relative_eq!(v, 0.25, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn bounds() {
+        let b = tri().bounds();
+        assert_relative_eq!(b.min, Point::new(-1, 0, 0));
+        assert_relative_eq!(b.max, Point::new(1, 1, 0));
+    }
+
+    #[test]
+    fn sample_surface() {
+        let t = tri();
+        assert_relative_eq!(t.surface_area().unwrap(), 1.0);
+        for i in 0..10 {
+            for j in 0..10 {
+                let (p, n) = t.sample_surface((i as f64 + 0.5) / 10.0, (j as f64 + 0.5) / 10.0);
+                assert_relative_eq!(p.z, 0.0);
+                assert!(p.y > -1e-12 && p.y < 1.0 - p.x.abs() + 1e-12);
+                assert_relative_eq!(n, t.normal(p));
+            }
+        }
+    }
+}
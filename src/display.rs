@@ -1,4 +1,4 @@
-use crate::{spaces, Camera, Point, RayColor, Vector};
+use crate::{spaces, Camera, Point, RayColor, ToneMap, Vector};
 use softbuffer::GraphicsContext;
 use winit::event::{DeviceEvent, Event, VirtualKeyCode, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
@@ -48,7 +48,9 @@ pub fn display(
                 buffer = vec![0x102030u32; (width * height) as usize];
                 graphics_context.set_buffer(&buffer, width as u16, height as u16);
                 let camera = Camera::new(width, height, fov, from, to, up, oversample);
-                camera.u32_buffer(&world, &mut buffer[..]);
+                camera
+                    .render(&world)
+                    .to_u32_buffer(ToneMap::default(), &mut buffer[..]);
                 cur_width = width;
                 cur_height = height;
             }
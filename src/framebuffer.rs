@@ -0,0 +1,178 @@
+use crate::tonemap::srgb_decode;
+use crate::Color;
+use image::Rgb32FImage;
+use std::path::Path;
+
+/// A rectangular buffer of colors, such as a rendered image.  Unlike an 8-bit image, colors
+/// are unbounded, so nothing is lost before the buffer is saved or tone-mapped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+
+    /// Pixels in row-major order, starting at the top left
+    pixels: Vec<Color>,
+}
+
+impl Framebuffer {
+    /// Create a black framebuffer of the given size.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![Color::black(); width as usize * height as usize],
+        }
+    }
+
+    /// Create a framebuffer from pixels in row-major order.
+    pub(crate) fn from_pixels(width: u32, height: u32, pixels: Vec<Color>) -> Self {
+        assert_eq!(pixels.len(), width as usize * height as usize);
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Check whether this framebuffer has no pixels.
+    pub fn is_empty(&self) -> bool {
+        self.pixels.is_empty()
+    }
+
+    /// Load an image file: a Radiance `.hdr` file, a `.ppm` file, or a PNG.  The values of an
+    /// 8-bit PNG are sRGB-encoded, and are converted to linear colors.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, image::ImageError> {
+        Self::load_image(path.as_ref(), true)
+    }
+
+    /// Load an image file as for `load`, but without converting 8-bit PNGs from sRGB, for
+    /// images which hold data rather than colors, such as normal maps.
+    pub fn load_linear<P: AsRef<Path>>(path: P) -> Result<Self, image::ImageError> {
+        Self::load_image(path.as_ref(), false)
+    }
+
+    fn load_image(path: &Path, srgb: bool) -> Result<Self, image::ImageError> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+        Ok(match extension.as_deref() {
+            Some("hdr") => Self::load_hdr(path)?,
+            Some("ppm") => Self::load_ppm(path)?,
+            _ => {
+                let img = image::open(path)?;
+                let color = img.color();
+                let mut fb = Self::from(&img.to_rgb32f());
+                if srgb && color.bytes_per_pixel() == color.channel_count() {
+                    for c in fb.pixels_mut() {
+                        *c = Color::new(
+                            srgb_decode(c.red),
+                            srgb_decode(c.green),
+                            srgb_decode(c.blue),
+                        );
+                    }
+                }
+                fb
+            }
+        })
+    }
+
+    /// Get the color of the pixel at the given coordinates.
+    pub fn get(&self, x: u32, y: u32) -> Color {
+        self.pixels[self.index(x, y)]
+    }
+
+    /// Set the color of the pixel at the given coordinates.
+    pub fn set(&mut self, x: u32, y: u32, color: Color) {
+        let i = self.index(x, y);
+        self.pixels[i] = color;
+    }
+
+    /// Get all pixels, in row-major order.
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    /// Get all pixels mutably, in row-major order.
+    pub fn pixels_mut(&mut self) -> &mut [Color] {
+        &mut self.pixels
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        debug_assert!(x < self.width && y < self.height);
+        y as usize * self.width as usize + x as usize
+    }
+}
+
+impl From<&Rgb32FImage> for Framebuffer {
+    fn from(img: &Rgb32FImage) -> Self {
+        Self {
+            width: img.width(),
+            height: img.height(),
+            pixels: img
+                .pixels()
+                .map(|p| Color::new(p.0[0], p.0[1], p.0[2]))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::*;
+    use image::{Rgb, RgbImage};
+
+    #[test]
+    fn new_is_black() {
+        let fb = Framebuffer::new(3, 2);
+        assert_eq!((fb.width(), fb.height()), (3, 2));
+        assert!(fb.pixels().iter().all(|&c| c == Color::black()));
+    }
+
+    #[test]
+    fn get_and_set() {
+        let mut fb = Framebuffer::new(3, 2);
+        fb.set(2, 1, Color::new(1, 2, 3));
+        assert_eq!(fb.get(2, 1), Color::new(1, 2, 3));
+        assert_eq!(fb.pixels()[5], Color::new(1, 2, 3));
+    }
+
+    #[test]
+    fn from_image() {
+        let img = Rgb32FImage::from_fn(2, 2, |x, y| Rgb([x as f32, y as f32, 0.5]));
+        let fb = Framebuffer::from(&img);
+        assert_eq!(fb.get(1, 0), Color::new(1, 0, 0.5));
+        assert_eq!(fb.get(0, 1), Color::new(0, 1, 0.5));
+    }
+
+    #[test]
+    fn load_srgb_png() {
+        let path = std::env::temp_dir().join(format!("rtc-grey-{}.png", std::process::id()));
+        RgbImage::from_pixel(1, 1, Rgb([128, 128, 128]))
+            .save(&path)
+            .unwrap();
+        let fb = Framebuffer::load(&path).unwrap();
+        let linear = Framebuffer::load_linear(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        // mid-grey in sRGB is about a fifth of full intensity
+        assert_relative_eq!(
+            fb.get(0, 0),
+            Color::new(0.21586, 0.21586, 0.21586),
+            epsilon = 1e-5
+        );
+        assert_relative_eq!(
+            linear.get(0, 0),
+            Color::new(128, 128, 128) / 255.0,
+            epsilon = 1e-6
+        );
+    }
+}
@@ -0,0 +1,378 @@
+use crate::{Color, Framebuffer};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Scanlines of this width or more (and less than 0x8000) are run-length encoded.
+const MIN_RLE_WIDTH: u32 = 8;
+const MAX_RLE_WIDTH: u32 = 0x7fff;
+
+/// Wider images are rejected rather than allocating a scanline for them.
+const MAX_WIDTH: u32 = 0x10000;
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Encode a color as RGBE: an 8-bit mantissa for each of red, green and blue, with a shared
+/// 8-bit exponent, so unbounded colors survive with about 1% precision.  Negative components
+/// are treated as zero.
+fn to_rgbe(c: Color) -> [u8; 4] {
+    let (r, g, b) = (c.red.max(0.0), c.green.max(0.0), c.blue.max(0.0));
+    let v = r.max(g).max(b);
+    if v < 1e-32 {
+        return [0; 4];
+    }
+    // v = m * 2^e, with m in [0.5, 1)
+    let mut e = v.log2().floor() as i32 + 1;
+    if v / 2f64.powi(e) >= 1.0 {
+        e += 1;
+    }
+    let scale = 256.0 / 2f64.powi(e);
+    [
+        (r * scale) as u8,
+        (g * scale) as u8,
+        (b * scale) as u8,
+        (e + 128).clamp(0, 255) as u8,
+    ]
+}
+
+/// Decode an RGBE pixel to a color.
+fn from_rgbe([r, g, b, e]: [u8; 4]) -> Color {
+    if e == 0 {
+        return Color::black();
+    }
+    let f = 2f64.powi(e as i32 - (128 + 8));
+    Color::new(
+        (r as f64 + 0.5) * f,
+        (g as f64 + 0.5) * f,
+        (b as f64 + 0.5) * f,
+    )
+}
+
+impl Framebuffer {
+    /// Read a Radiance `.hdr` file.
+    pub fn load_hdr<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read_hdr(BufReader::new(File::open(path)?))
+    }
+
+    /// Write this framebuffer to a Radiance `.hdr` file.
+    pub fn save_hdr<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.write_hdr(&mut w)?;
+        w.flush()
+    }
+
+    /// Read an image in Radiance RGBE format.
+    pub fn read_hdr(mut r: impl BufRead) -> io::Result<Self> {
+        let mut line = String::new();
+        r.read_line(&mut line)?;
+        if !line.starts_with("#?") {
+            return Err(invalid_data("not a Radiance HDR file"));
+        }
+
+        // header lines, ending with a blank line
+        loop {
+            line.clear();
+            if r.read_line(&mut line)? == 0 {
+                return Err(invalid_data("unexpected end of header"));
+            }
+            let line = line.trim();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(format) = line.strip_prefix("FORMAT=") {
+                if format != "32-bit_rle_rgbe" {
+                    return Err(invalid_data(format!("unsupported format {}", format)));
+                }
+            }
+        }
+
+        line.clear();
+        r.read_line(&mut line)?;
+        let (width, height) = match line.split_whitespace().collect::<Vec<_>>()[..] {
+            ["-Y", h, "+X", w] => match (w.parse(), h.parse()) {
+                (Ok(w), Ok(h)) => (w, h),
+                _ => return Err(invalid_data(format!("invalid size {:?}", line.trim()))),
+            },
+            _ => {
+                return Err(invalid_data(format!(
+                    "unsupported orientation {:?}",
+                    line.trim()
+                )))
+            }
+        };
+
+        if width > MAX_WIDTH {
+            return Err(invalid_data(format!("image too wide {:?}", line.trim())));
+        }
+
+        // the pixels grow as scanlines are read, rather than being allocated up front, so that
+        // a corrupt header can't ask for more memory than the data fills
+        let mut pixels = vec![];
+        let mut scanline = vec![[0u8; 4]; width as usize];
+        let rows = if width == 0 { 0 } else { height };
+        for _ in 0..rows {
+            read_scanline(&mut r, &mut scanline)?;
+            pixels.extend(scanline.iter().map(|&rgbe| from_rgbe(rgbe)));
+        }
+        Ok(Framebuffer::from_pixels(width, height, pixels))
+    }
+
+    /// Write this framebuffer in Radiance RGBE format, with run-length encoded scanlines.
+    pub fn write_hdr(&self, mut w: impl Write) -> io::Result<()> {
+        write!(
+            w,
+            "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            self.height(),
+            self.width()
+        )?;
+        let width = self.width();
+        let rle = (MIN_RLE_WIDTH..=MAX_RLE_WIDTH).contains(&width);
+        let mut component = Vec::with_capacity(width as usize);
+        for row in self.pixels().chunks(width.max(1) as usize) {
+            let rgbe: Vec<[u8; 4]> = row.iter().map(|&c| to_rgbe(c)).collect();
+            if !rle {
+                w.write_all(&rgbe.concat())?;
+                continue;
+            }
+            w.write_all(&[2, 2, (width >> 8) as u8, width as u8])?;
+            for i in 0..4 {
+                component.clear();
+                component.extend(rgbe.iter().map(|p| p[i]));
+                write_rle(&mut w, &component)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read a single scanline, in any of flat, old-style run-length or new-style run-length
+/// encodings.
+fn read_scanline(r: &mut impl Read, scanline: &mut [[u8; 4]]) -> io::Result<()> {
+    let width = scanline.len();
+    if width == 0 {
+        return Ok(());
+    }
+    let mut first = [0u8; 4];
+    r.read_exact(&mut first)?;
+
+    let rle_width = (MIN_RLE_WIDTH as usize..=MAX_RLE_WIDTH as usize).contains(&width);
+    if !rle_width || first[0] != 2 || first[1] != 2 || first[2] & 0x80 != 0 {
+        return read_flat_scanline(r, first, scanline);
+    }
+    if (first[2] as usize) << 8 | first[3] as usize != width {
+        return Err(invalid_data("scanline width mismatch"));
+    }
+
+    // each component is run-length encoded separately
+    for i in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let mut count = [0u8; 1];
+            r.read_exact(&mut count)?;
+            let count = count[0] as usize;
+            if count > 128 {
+                let count = count - 128;
+                if x + count > width {
+                    return Err(invalid_data("run overruns scanline"));
+                }
+                let mut value = [0u8; 1];
+                r.read_exact(&mut value)?;
+                for p in &mut scanline[x..x + count] {
+                    p[i] = value[0];
+                }
+                x += count;
+            } else {
+                if count == 0 || x + count > width {
+                    return Err(invalid_data("invalid run in scanline"));
+                }
+                let mut values = vec![0u8; count];
+                r.read_exact(&mut values)?;
+                for (p, v) in scanline[x..x + count].iter_mut().zip(values) {
+                    p[i] = v;
+                }
+                x += count;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Read a scanline of flat pixels, where a pixel of `[1, 1, 1, n]` repeats the previous pixel.
+fn read_flat_scanline(
+    r: &mut impl Read,
+    first: [u8; 4],
+    scanline: &mut [[u8; 4]],
+) -> io::Result<()> {
+    let mut x = 0;
+    let mut shift = 0;
+    let mut pixel = first;
+    loop {
+        if pixel[..3] == [1, 1, 1] {
+            if x == 0 {
+                return Err(invalid_data("repeat at start of scanline"));
+            }
+            let count = (pixel[3] as usize) << shift;
+            if x + count > scanline.len() {
+                return Err(invalid_data("run overruns scanline"));
+            }
+            let prev = scanline[x - 1];
+            scanline[x..x + count].fill(prev);
+            x += count;
+            shift += 8;
+        } else {
+            scanline[x] = pixel;
+            x += 1;
+            shift = 0;
+        }
+        if x == scanline.len() {
+            return Ok(());
+        }
+        r.read_exact(&mut pixel)?;
+    }
+}
+
+/// Write a single run-length encoded component of a scanline.  Runs shorter than 4 bytes are
+/// not worth encoding, so are included in literal spans.
+fn write_rle(w: &mut impl Write, data: &[u8]) -> io::Result<()> {
+    const MIN_RUN: usize = 4;
+    let mut cur = 0;
+    while cur < data.len() {
+        // find the next run of at least MIN_RUN bytes
+        let mut run_start = cur;
+        let mut run_len = 0;
+        while run_start < data.len() {
+            run_len = data[run_start..]
+                .iter()
+                .take(127)
+                .take_while(|&&b| b == data[run_start])
+                .count();
+            if run_len >= MIN_RUN {
+                break;
+            }
+            run_start += run_len;
+            run_len = 0;
+        }
+
+        // write the literal bytes before the run
+        while cur < run_start {
+            let len = (run_start - cur).min(128);
+            w.write_all(&[len as u8])?;
+            w.write_all(&data[cur..cur + len])?;
+            cur += len;
+        }
+
+        if run_len > 0 {
+            w.write_all(&[128 + run_len as u8, data[run_start]])?;
+            cur += run_len;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::*;
+
+    /// A framebuffer with a mix of runs and varied pixels, including very bright ones.
+    fn test_image(width: u32, height: u32) -> Framebuffer {
+        let mut fb = Framebuffer::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                if x >= width / 2 {
+                    let f = (x * y) as f64;
+                    fb.set(x, y, Color::new(f * 0.01, f * 10.0, 1.0 / (f + 1.0)));
+                }
+            }
+        }
+        fb
+    }
+
+    fn assert_close(a: &Framebuffer, b: &Framebuffer) {
+        assert_eq!((a.width(), a.height()), (b.width(), b.height()));
+        for (c1, c2) in a.pixels().iter().zip(b.pixels()) {
+            // each component is within 1% of the brightest component
+            let max = c1.red.max(c1.green).max(c1.blue);
+            for (v1, v2) in c1.iter().zip(c2.iter()) {
+                assert_abs_diff_eq!(v1, v2, epsilon = max * 0.01);
+            }
+        }
+    }
+
+    #[test]
+    fn rgbe_roundtrip() {
+        for c in [
+            Color::new(1, 0.5, 0.25),
+            Color::new(1000, 0, 3),
+            Color::new(0.001, 0.002, 0.003),
+            Color::new(0.5, 0.5, 0.5),
+        ] {
+            let d = from_rgbe(to_rgbe(c));
+            let max = c.red.max(c.green).max(c.blue);
+            for (v1, v2) in c.iter().zip(d.iter()) {
+                assert_abs_diff_eq!(v1, v2, epsilon = max * 0.01);
+            }
+        }
+        assert_eq!(to_rgbe(Color::black()), [0; 4]);
+        assert_eq!(from_rgbe([0; 4]), Color::black());
+        assert_eq!(to_rgbe(Color::new(1, 0.5, -1)), [128, 64, 0, 129]);
+    }
+
+    #[test]
+    fn roundtrip_rle() {
+        let fb = test_image(300, 5);
+        let mut buf = vec![];
+        fb.write_hdr(&mut buf).unwrap();
+        assert!(buf.starts_with(b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 5 +X 300\n"));
+        // the black half of each row is compressed
+        assert!(buf.len() < 300 * 5 * 4);
+        assert_close(&fb, &Framebuffer::read_hdr(&buf[..]).unwrap());
+    }
+
+    #[test]
+    fn roundtrip_flat() {
+        let fb = test_image(6, 3);
+        let mut buf = vec![];
+        fb.write_hdr(&mut buf).unwrap();
+        assert_eq!(buf.len(), 45 + 6 * 3 * 4);
+        assert_close(&fb, &Framebuffer::read_hdr(&buf[..]).unwrap());
+    }
+
+    #[test]
+    fn read_old_rle() {
+        let mut buf = b"#?RGBE\n\n-Y 1 +X 5\n".to_vec();
+        buf.extend([128, 64, 32, 129, 1, 1, 1, 3, 0, 0, 0, 0]);
+        let fb = Framebuffer::read_hdr(&buf[..]).unwrap();
+        for x in 0..4 {
+            assert_relative_eq!(fb.get(x, 0), Color::new(1.00390625, 0.50390625, 0.25390625));
+        }
+        assert_eq!(fb.get(4, 0), Color::black());
+    }
+
+    #[test]
+    fn read_errors() {
+        for (input, message) in [
+            (&b"P3\n"[..], "not a Radiance HDR file"),
+            (
+                b"#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n",
+                "unsupported format",
+            ),
+            (b"#?RADIANCE\n\n+Y 1 +X 1\n", "unsupported orientation"),
+            (b"#?RADIANCE\n", "unexpected end of header"),
+            (b"#?RADIANCE\n\n-Y 1 +X 100000\n", "image too wide"),
+        ] {
+            let err = Framebuffer::read_hdr(input).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert!(err.to_string().starts_with(message), "{}", err);
+        }
+        let err = Framebuffer::read_hdr(&b"#?RADIANCE\n\n-Y 1 +X 2\n\x80\x80"[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        // a huge size fails when the data runs out, without allocating for the whole image
+        let err =
+            Framebuffer::read_hdr(&b"#?RADIANCE\n\n-Y 65535 +X 65535\n\0\0\0\0"[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -10,6 +10,16 @@ pub struct Intersection {
 
     /// The intersected object
     pub object_index: ObjectIndex,
+
+    /// The (u, v) coordinates of the intersection on the surface of the object, for objects
+    /// which calculate them (such as triangles, where they are barycentric coordinates).
+    pub uv: Option<(f64, f64)>,
+
+    /// The object whose surface this is, when tracking which objects contain the ray.  This is
+    /// `object_index` for a lone primitive, but identifies the whole combination for a
+    /// primitive within a CSG object, whose surface the ray may enter through one primitive
+    /// and leave through another.
+    pub(crate) container: ObjectIndex,
 }
 
 /// Itersections maintains a mutable set of Intersection instances
@@ -36,7 +46,27 @@ impl Intersections {
 
     /// Add a new intersection to this set, using the current object
     pub fn add(&mut self, t: f64, object_index: ObjectIndex) {
-        self.hits.push(Intersection { t, object_index });
+        self.push(Intersection {
+            t,
+            object_index,
+            uv: None,
+            container: object_index,
+        });
+    }
+
+    /// Add a new intersection to this set, including its (u, v) coordinates
+    pub fn add_with_uv(&mut self, t: f64, object_index: ObjectIndex, u: f64, v: f64) {
+        self.push(Intersection {
+            t,
+            object_index,
+            uv: Some((u, v)),
+            container: object_index,
+        });
+    }
+
+    /// Add an existing intersection to this set
+    pub(crate) fn push(&mut self, intersection: Intersection) {
+        self.hits.push(intersection);
         self.sorted = false;
     }
 
@@ -44,6 +74,10 @@ impl Intersections {
         self.hits.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.hits.is_empty()
+    }
+
     pub fn clear(&mut self) {
         self.hits.clear();
     }
@@ -58,7 +92,17 @@ impl Intersections {
 
     /// Get the first intersection with a non-negative `t`, along with the object _from_
     /// and _to_ which that intersection occurs.
-    pub fn hit(&mut self) -> (Option<ObjectIndex>, Option<f64>, Option<ObjectIndex>) {
+    ///
+    /// The from and to objects are the innermost objects containing the ray before and after
+    /// the intersection, which are not necessarily the object that was hit.  Within a CSG
+    /// object, this is the primitive through which the ray entered it.
+    pub fn hit(
+        &mut self,
+    ) -> (
+        Option<ObjectIndex>,
+        Option<Intersection>,
+        Option<ObjectIndex>,
+    ) {
         self.sort();
 
         // TODO: do this without allocations?
@@ -69,9 +113,7 @@ impl Intersections {
             if h.t >= 0.0 {
                 from_obj = containers.last().map(|i| i.object_index);
             }
-            if let Some(container_idx) = containers
-                .iter()
-                .position(|i| i.object_index == h.object_index)
+            if let Some(container_idx) = containers.iter().position(|i| i.container == h.container)
             {
                 // this object was in containers, so we are exiting the object
                 containers.remove(container_idx);
@@ -80,14 +122,14 @@ impl Intersections {
             }
             if h.t >= 0.0 {
                 let last = containers.last();
-                return (from_obj, Some(h.t), last.map(|i| i.object_index));
+                return (from_obj, Some(*h), last.map(|i| i.object_index));
             }
         }
         (from_obj, None, None)
     }
 
     /// Get an iterator over all hits, in order by `t`
-    pub fn iter(&mut self) -> std::slice::Iter<Intersection> {
+    pub fn iter(&mut self) -> std::slice::Iter<'_, Intersection> {
         self.sort();
         self.hits.iter()
     }
@@ -104,19 +146,30 @@ mod test {
         let i = Intersection {
             t: 1.0,
             object_index: ObjectIndex::test_value(0),
+            uv: None,
+            container: ObjectIndex::test_value(0),
         };
         assert_relative_eq!(i.t, 1.0);
         assert_eq!(i.object_index, ObjectIndex::test_value(0));
     }
 
+    #[test]
+    fn intersection_with_uv() {
+        let mut inters = Intersections::default();
+        inters.add_with_uv(3.5, ObjectIndex::test_value(2), 0.2, 0.4);
+        let i = inters.iter().next().unwrap();
+        assert_relative_eq!(i.t, 3.5);
+        assert_eq!(i.uv, Some((0.2, 0.4)));
+    }
+
     #[test]
     fn hit_all_positive_t() {
         let mut inters = Intersections::default();
         inters.add(1.0, ObjectIndex::test_value(1));
         inters.add(2.0, ObjectIndex::test_value(2));
-        let (from_obj, t, to_obj) = inters.hit();
+        let (from_obj, hit, to_obj) = inters.hit();
         assert_eq!(from_obj, None);
-        assert_relative_eq!(t.unwrap(), 1.0);
+        assert_relative_eq!(hit.unwrap().t, 1.0);
         assert_eq!(to_obj, Some(ObjectIndex::test_value(1)));
     }
 
@@ -125,9 +178,9 @@ mod test {
         let mut inters = Intersections::default();
         inters.add(-1.0, ObjectIndex::test_value(1));
         inters.add(1.0, ObjectIndex::test_value(2));
-        let (from_obj, t, to_obj) = inters.hit();
+        let (from_obj, hit, to_obj) = inters.hit();
         assert_eq!(from_obj, Some(ObjectIndex::test_value(1)));
-        assert_relative_eq!(t.unwrap(), 1.0);
+        assert_relative_eq!(hit.unwrap().t, 1.0);
         assert_eq!(to_obj, Some(ObjectIndex::test_value(2)));
     }
 
@@ -136,9 +189,9 @@ mod test {
         let mut inters = Intersections::default();
         inters.add(-1.0, ObjectIndex::test_value(1));
         inters.add(-2.0, ObjectIndex::test_value(1));
-        let (from_obj, t, to_obj) = inters.hit();
+        let (from_obj, hit, to_obj) = inters.hit();
         assert_eq!(from_obj, None);
-        assert_eq!(t, None);
+        assert!(hit.is_none());
         assert_eq!(to_obj, None);
     }
 
@@ -149,9 +202,9 @@ mod test {
         inters.add(7.0, ObjectIndex::test_value(2));
         inters.add(-3.0, ObjectIndex::test_value(3));
         inters.add(2.0, ObjectIndex::test_value(4));
-        let (from_obj, t, to_obj) = inters.hit();
+        let (from_obj, hit, to_obj) = inters.hit();
         assert_eq!(from_obj, Some(ObjectIndex::test_value(3)));
-        assert_relative_eq!(t.unwrap(), 2.0);
+        assert_relative_eq!(hit.unwrap().t, 2.0);
         assert_eq!(to_obj, Some(ObjectIndex::test_value(4)));
     }
 
@@ -205,8 +258,10 @@ mod test {
         let mut inters = Intersections::default();
         let r: Ray<spaces::World> = Ray::new(Point::new(0, 0, 1), Vector::new(0, 0, 1));
         w.intersect(&r, &mut inters);
-        let (from_obj, _, to_obj) = inters.hit();
+        let (from_obj, hit, to_obj) = inters.hit();
         assert_eq!((from_obj, to_obj), (Some(c), Some(a)));
+        // the surface hit is that of c, as the ray exits it
+        assert_eq!(hit.unwrap().object_index, c);
 
         let mut inters = Intersections::default();
         let r: Ray<spaces::World> = Ray::new(Point::new(0, 0, 2), Vector::new(0, 0, 1));
@@ -1,22 +1,47 @@
+mod background;
+mod bounds;
+mod bvh;
 mod camera;
 mod colors;
 pub mod csg;
 mod display;
+mod framebuffer;
+mod hdr;
 mod intersect;
+mod light;
 mod material;
 mod math;
+pub mod noise;
+mod normal_modifier;
+mod path_tracer;
 mod pattern;
+mod ppm;
 mod ray;
+mod scene;
 pub mod sdf;
 pub mod spaces;
+mod texture;
+mod tonemap;
+mod uv;
 mod world;
 
+pub use background::*;
+pub use bounds::*;
 pub use camera::*;
 pub use colors::*;
 pub use display::*;
+pub use framebuffer::*;
 pub use intersect::*;
+pub use light::*;
 pub use material::*;
 pub use math::*;
+pub use normal_modifier::*;
+pub use path_tracer::*;
 pub use pattern::*;
+pub use ppm::*;
 pub use ray::*;
+pub use scene::*;
+pub use texture::*;
+pub use tonemap::*;
+pub use uv::*;
 pub use world::*;
@@ -0,0 +1,478 @@
+use crate::math::splitmix64;
+use crate::{spaces, Color, Point, Vector};
+
+/// The smallest distance at which attenuation is calculated; points closer to the light than
+/// this are lit as if they were at this distance, rather than infinitely brightly.
+const MIN_ATTENUATION_DISTANCE: f64 = 1e-3;
+
+/// A representation of a light in the world.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Light {
+    /// The position of the light.  For an area light, this is its center.  A directional
+    /// light has no position, and this is unused.
+    pub position: Point<spaces::World>,
+    pub intensity: Color,
+
+    /// How the light falls off with distance from its position.
+    pub attenuation: Attenuation,
+    pub(crate) kind: LightKind,
+}
+
+/// A model of how a light's intensity falls off with distance.  A light's intensity is that
+/// seen at a distance of one unit.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Attenuation {
+    /// The intensity is the same at any distance
+    #[default]
+    None,
+
+    /// The intensity falls off as `1 / d`
+    Linear,
+
+    /// The intensity falls off as `1 / d^2`, as for a physical point light
+    InverseSquare,
+
+    /// The intensity falls off as `1 / (constant + linear * d + quadratic * d^2)`
+    Custom {
+        constant: f64,
+        linear: f64,
+        quadratic: f64,
+    },
+}
+
+impl Attenuation {
+    /// Get the fraction of a light's intensity remaining at the given distance.
+    pub fn factor(&self, distance: f64) -> f64 {
+        let distance = distance.max(MIN_ATTENUATION_DISTANCE);
+        let (constant, linear, quadratic) = match *self {
+            Attenuation::None => return 1.0,
+            Attenuation::Linear => (0.0, 1.0, 0.0),
+            Attenuation::InverseSquare => (0.0, 0.0, 1.0),
+            Attenuation::Custom {
+                constant,
+                linear,
+                quadratic,
+            } => (constant, linear, quadratic),
+        };
+        1.0 / (constant + linear * distance + quadratic * distance * distance)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub(crate) enum LightKind {
+    /// All light is emitted from `position`
+    #[default]
+    Point,
+
+    /// Light is emitted from a rectangle, sampled at one point in each cell of a grid
+    Area {
+        corner: Point<spaces::World>,
+        /// The edges of one cell of the grid
+        uvec: Vector<spaces::World>,
+        vvec: Vector<spaces::World>,
+        usteps: u32,
+        vsteps: u32,
+        /// If true, sample a pseudo-random point in each cell, rather than its center
+        jitter: bool,
+    },
+
+    /// Light is emitted from `position` in a cone around `direction`, fading smoothly between
+    /// the inner and outer cone angles
+    Spot {
+        direction: Vector<spaces::World>,
+        /// Cosines of the inner and outer cone angles
+        cos_inner: f64,
+        cos_outer: f64,
+    },
+
+    /// Light arrives from infinitely far away along `direction`, in parallel rays
+    Directional { direction: Vector<spaces::World> },
+}
+
+/// A representation of light's effect at a particular point
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub(crate) struct LightAt {
+    /// The intensity of the light
+    pub(crate) intensity: Color,
+
+    /// Direction from the given point to the light
+    pub(crate) direction: Vector<spaces::World>,
+
+    /// The fraction of each component of the light reaching the point, from black (in shadow)
+    /// to white (fully lit).  Light filtered through transparent objects is tinted.
+    pub(crate) visibility: Color,
+}
+
+impl Light {
+    pub fn new_point(position: Point<spaces::World>, intensity: Color) -> Self {
+        Self {
+            position,
+            intensity,
+            attenuation: Attenuation::None,
+            kind: LightKind::Point,
+        }
+    }
+
+    /// Create a rectangular light with the given corner and edges.  Shadows are calculated
+    /// by sampling a grid of `usteps` by `vsteps` points on the light, giving soft edges.  Panics
+    /// if either step count is zero.
+    pub fn new_area(
+        corner: Point<spaces::World>,
+        full_uvec: Vector<spaces::World>,
+        usteps: u32,
+        full_vvec: Vector<spaces::World>,
+        vsteps: u32,
+        intensity: Color,
+    ) -> Self {
+        assert!(
+            usteps > 0 && vsteps > 0,
+            "area light needs at least one step in each direction"
+        );
+        Self {
+            position: corner + full_uvec / 2.0 + full_vvec / 2.0,
+            intensity,
+            attenuation: Attenuation::None,
+            kind: LightKind::Area {
+                corner,
+                uvec: full_uvec / usteps as f64,
+                vvec: full_vvec / vsteps as f64,
+                usteps,
+                vsteps,
+                jitter: false,
+            },
+        }
+    }
+
+    /// Return an updated light which, if it is an area light, samples a pseudo-random point
+    /// within each cell of its grid rather than the cell's center.  This replaces banding in
+    /// soft shadows with noise.
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        if let LightKind::Area { jitter: j, .. } = &mut self.kind {
+            *j = jitter;
+        }
+        self
+    }
+
+    /// Create a spot light at the given position, shining along the given direction.  The
+    /// light is at full intensity within `inner_angle` of that direction, and fades smoothly
+    /// to nothing at `outer_angle`.  Angles are in radians.
+    pub fn new_spot(
+        position: Point<spaces::World>,
+        direction: Vector<spaces::World>,
+        inner_angle: f64,
+        outer_angle: f64,
+        intensity: Color,
+    ) -> Self {
+        Self {
+            position,
+            intensity,
+            attenuation: Attenuation::None,
+            kind: LightKind::Spot {
+                direction: direction.normalize(),
+                cos_inner: inner_angle.cos(),
+                cos_outer: outer_angle.cos(),
+            },
+        }
+    }
+
+    /// Create a directional light, such as the sun, shining along the given direction from
+    /// infinitely far away.
+    pub fn new_directional(direction: Vector<spaces::World>, intensity: Color) -> Self {
+        Self {
+            position: Point::default(),
+            intensity,
+            attenuation: Attenuation::None,
+            kind: LightKind::Directional {
+                direction: direction.normalize(),
+            },
+        }
+    }
+
+    /// Return an updated light whose intensity falls off with distance according to the given
+    /// model.  This has no effect on a directional light.
+    pub fn with_attenuation(mut self, attenuation: Attenuation) -> Self {
+        self.attenuation = attenuation;
+        self
+    }
+
+    /// Get the fraction of this light's intensity which falls on the given point, ignoring
+    /// shadows.
+    pub(crate) fn intensity_factor(&self, point: Point<spaces::World>) -> f64 {
+        let attenuation = match self.kind {
+            LightKind::Directional { .. } => 1.0,
+            _ => self.attenuation.factor((self.position - point).magnitude()),
+        };
+        attenuation * self.cone_factor(point)
+    }
+
+    /// Get the fraction of a spot light's intensity falling on the given point, due to its
+    /// cone.  This is 1 for other kinds of light.
+    fn cone_factor(&self, point: Point<spaces::World>) -> f64 {
+        match self.kind {
+            LightKind::Spot {
+                direction,
+                cos_inner,
+                cos_outer,
+            } => {
+                let cos = (point - self.position).normalize().dot(direction);
+                if cos_inner <= cos_outer {
+                    // no falloff, so the cone has a hard edge
+                    return if cos >= cos_outer { 1.0 } else { 0.0 };
+                }
+                let t = ((cos - cos_outer) / (cos_inner - cos_outer)).clamp(0.0, 1.0);
+                t * t * (3.0 - 2.0 * t)
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// Get the (normalized) direction from the given point toward this light.
+    pub(crate) fn direction_from(&self, point: Point<spaces::World>) -> Vector<spaces::World> {
+        match self.kind {
+            LightKind::Directional { direction } => -direction,
+            _ => (self.position - point).normalize(),
+        }
+    }
+
+    /// Get the (normalized) direction and distance from the given point to the `i`th point
+    /// sampled on this light when calculating shadows.  The distance to a directional light is
+    /// infinite.
+    pub(crate) fn shadow_ray(
+        &self,
+        i: u32,
+        from: Point<spaces::World>,
+    ) -> (Vector<spaces::World>, f64) {
+        match self.kind {
+            LightKind::Directional { direction } => (-direction, f64::INFINITY),
+            _ => {
+                let v = self.sample_point(i, from) - from;
+                let distance = v.magnitude();
+                (v / distance, distance)
+            }
+        }
+    }
+
+    /// Get the number of points sampled on this light when calculating shadows.
+    pub(crate) fn samples(&self) -> u32 {
+        match self.kind {
+            LightKind::Point | LightKind::Spot { .. } | LightKind::Directional { .. } => 1,
+            LightKind::Area { usteps, vsteps, .. } => usteps * vsteps,
+        }
+    }
+
+    /// Get the `i`th point sampled on this light when calculating shadows at the given point.
+    pub(crate) fn sample_point(&self, i: u32, from: Point<spaces::World>) -> Point<spaces::World> {
+        match self.kind {
+            LightKind::Point | LightKind::Spot { .. } | LightKind::Directional { .. } => {
+                self.position
+            }
+            LightKind::Area {
+                corner,
+                uvec,
+                vvec,
+                usteps,
+                jitter,
+                ..
+            } => {
+                let (u, v) = ((i % usteps) as f64, (i / usteps) as f64);
+                let (ju, jv) = if jitter {
+                    (hash01(from, i, 0), hash01(from, i, 1))
+                } else {
+                    (0.5, 0.5)
+                };
+                corner + uvec * (u + ju) + vvec * (v + jv)
+            }
+        }
+    }
+}
+
+/// A pseudo-random number in [0, 1), determined by the given point, sample and axis, so that
+/// renders are repeatable.
+fn hash01(p: Point<spaces::World>, i: u32, axis: u64) -> f64 {
+    let h = splitmix64(
+        p.x.to_bits()
+            ^ p.y.to_bits().rotate_left(21)
+            ^ p.z.to_bits().rotate_left(42)
+            ^ ((i as u64) << 1 | axis).wrapping_mul(0x9e3779b97f4a7c15),
+    );
+    (h >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::*;
+    use std::f64::consts::PI;
+
+    fn area() -> Light {
+        Light::new_area(
+            Point::new(0, 0, 0),
+            Vector::new(2, 0, 0),
+            4,
+            Vector::new(0, 0, 1),
+            2,
+            Color::white(),
+        )
+    }
+
+    #[test]
+    fn new_area() {
+        let light = area();
+        assert_relative_eq!(light.position, Point::new(1, 0, 0.5));
+        assert_eq!(light.samples(), 8);
+        let LightKind::Area { uvec, vvec, .. } = light.kind else {
+            panic!("expected an area light");
+        };
+        assert_relative_eq!(uvec, Vector::new(0.5, 0, 0));
+        assert_relative_eq!(vvec, Vector::new(0, 0, 0.5));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one step")]
+    fn new_area_without_steps() {
+        Light::new_area(
+            Point::new(0, 0, 0),
+            Vector::new(1, 0, 0),
+            0,
+            Vector::new(0, 0, 1),
+            2,
+            Color::white(),
+        );
+    }
+
+    #[test]
+    fn sample_point() {
+        let light = area();
+        let from = Point::new(0, 5, 0);
+        for (u, v, expected) in [
+            (0, 0, Point::new(0.25, 0, 0.25)),
+            (1, 0, Point::new(0.75, 0, 0.25)),
+            (0, 1, Point::new(0.25, 0, 0.75)),
+            (2, 0, Point::new(1.25, 0, 0.25)),
+            (3, 1, Point::new(1.75, 0, 0.75)),
+        ] {
+            assert_relative_eq!(light.sample_point(v * 4 + u, from), expected);
+        }
+    }
+
+    #[test]
+    fn jittered_sample_point() {
+        let light = area().with_jitter(true);
+        let from = Point::new(0, 5, 0);
+        for i in 0..light.samples() {
+            let p = light.sample_point(i, from);
+            let (u, v) = ((i % 4) as f64, (i / 4) as f64);
+            // within the cell, and repeatable
+            assert!(p.x >= u * 0.5 && p.x < (u + 1.0) * 0.5);
+            assert!(p.z >= v * 0.5 && p.z < (v + 1.0) * 0.5);
+            assert_eq!(p, light.sample_point(i, from));
+        }
+        assert_ne!(light.sample_point(0, from), area().sample_point(0, from));
+    }
+
+    #[test]
+    fn point_light_samples() {
+        let light = Light::new_point(Point::new(1, 2, 3), Color::white());
+        assert_eq!(light.samples(), 1);
+        assert_eq!(light.sample_point(0, Point::new(0, 0, 0)), light.position);
+        assert_eq!(light.with_jitter(true), light);
+    }
+
+    #[test]
+    fn spot_light_falloff() {
+        let light = Light::new_spot(
+            Point::new(0, 0, 0),
+            Vector::new(0, 0, 2),
+            PI / 6.0,
+            PI / 3.0,
+            Color::white(),
+        );
+        assert_relative_eq!(light.intensity_factor(Point::new(0, 0, 5)), 1.0);
+        assert_relative_eq!(light.intensity_factor(Point::new(1, 0, 5)), 1.0);
+        assert_relative_eq!(light.intensity_factor(Point::new(0, 5, 1)), 0.0);
+        assert_relative_eq!(light.intensity_factor(Point::new(0, 0, -5)), 0.0);
+        // at 45 degrees, between the cone angles
+        let t = (2f64.sqrt() / 2.0 - 0.5) / (3f64.sqrt() / 2.0 - 0.5);
+        assert_relative_eq!(
+            light.intensity_factor(Point::new(0, 1, 1)),
+            t * t * (3.0 - 2.0 * t),
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn spot_light_hard_edge() {
+        let light = Light::new_spot(
+            Point::new(0, 0, 0),
+            Vector::new(0, 0, 1),
+            PI / 4.0,
+            PI / 4.0,
+            Color::white(),
+        );
+        assert_relative_eq!(light.intensity_factor(Point::new(0, 0.9, 1)), 1.0);
+        assert_relative_eq!(light.intensity_factor(Point::new(0, 1.1, 1)), 0.0);
+    }
+
+    #[test]
+    fn directional_light() {
+        let light = Light::new_directional(Vector::new(0, -2, 0), Color::white());
+        let from = Point::new(3, 4, 5);
+        assert_relative_eq!(light.direction_from(from), Vector::new(0, 1, 0));
+        assert_eq!(
+            light.shadow_ray(0, from),
+            (Vector::new(0, 1, 0), f64::INFINITY)
+        );
+        assert_relative_eq!(light.intensity_factor(from), 1.0);
+        let light = light.with_attenuation(Attenuation::InverseSquare);
+        assert_relative_eq!(light.intensity_factor(from), 1.0);
+    }
+
+    #[test]
+    fn attenuation() {
+        for (attenuation, expected) in [
+            (Attenuation::None, 1.0),
+            (Attenuation::Linear, 0.25),
+            (Attenuation::InverseSquare, 0.0625),
+            (
+                Attenuation::Custom {
+                    constant: 1.0,
+                    linear: 0.5,
+                    quadratic: 0.125,
+                },
+                0.2,
+            ),
+        ] {
+            assert_relative_eq!(attenuation.factor(4.0), expected);
+            let light =
+                Light::new_point(Point::new(0, 4, 0), Color::white()).with_attenuation(attenuation);
+            assert_relative_eq!(light.intensity_factor(Point::new(0, 0, 0)), expected);
+        }
+    }
+
+    #[test]
+    fn attenuation_at_light() {
+        for (attenuation, expected) in [
+            (Attenuation::None, 1.0),
+            (Attenuation::Linear, 1e3),
+            (Attenuation::InverseSquare, 1e6),
+            (
+                Attenuation::Custom {
+                    constant: 0.0,
+                    linear: 0.0,
+                    quadratic: 2.0,
+                },
+                5e5,
+            ),
+            (
+                Attenuation::Custom {
+                    constant: 1.0,
+                    linear: 1.0,
+                    quadratic: 1.0,
+                },
+                1.0 / 1.001001,
+            ),
+        ] {
+            assert_relative_eq!(attenuation.factor(0.0), expected, max_relative = 1e-9);
+        }
+    }
+}
@@ -1,16 +1,18 @@
-use crate::{spaces, Color, Pattern, Point, Ray, Vector, World};
+use crate::{spaces, Color, LightAt, NormalModifier, Pattern, Point, Ray, Vector, World};
 
 /// Material defines the relevant characteristics of a material.
 #[derive(Debug, Clone)]
 pub struct Material {
-    pattern: Pattern,
-    ambient: f64,
-    diffuse: f64,
-    specular: f64,
-    shininess: f64,
-    reflectivity: f64,
-    transparency: f64,
-    refractive_index: f64,
+    pub(crate) pattern: Pattern,
+    pub(crate) ambient: f64,
+    pub(crate) diffuse: f64,
+    pub(crate) specular: f64,
+    pub(crate) shininess: f64,
+    pub(crate) reflectivity: f64,
+    pub(crate) transparency: f64,
+    pub(crate) refractive_index: f64,
+    pub(crate) normal_modifier: Option<NormalModifier>,
+    pub(crate) emission: Color,
 }
 
 impl Default for Material {
@@ -24,6 +26,8 @@ impl Default for Material {
             reflectivity: 0.0,
             transparency: 0.0,
             refractive_index: 1.0,
+            normal_modifier: None,
+            emission: Color::black(),
         }
     }
 }
@@ -39,6 +43,9 @@ impl Material {
         self
     }
 
+    /// Return an updated material reflecting the given fraction of ambient light.  Ambient light
+    /// is the same everywhere: the strongest intensity among the world's lights, ignoring their
+    /// attenuation, spot cones and shadows.
     pub fn with_ambient(mut self, ambient: f64) -> Self {
         self.ambient = ambient;
         self
@@ -71,6 +78,43 @@ impl Material {
         self
     }
 
+    /// Return an updated material which emits light of the given color, seen by any ray
+    /// which hits it, as for neon signs or light panels.  Only `PathTracer` also finds the
+    /// light it casts on other objects.
+    pub fn with_emission(mut self, emission: Color) -> Self {
+        self.emission = emission;
+        self
+    }
+
+    /// Return an updated material whose normals are perturbed by the given modifier, such as
+    /// a bump or normal map.
+    pub fn with_normal_modifier(mut self, normal_modifier: NormalModifier) -> Self {
+        self.normal_modifier = Some(normal_modifier);
+        self
+    }
+
+    /// Get the normal of this material at the given point on a surface with the given normal,
+    /// both in object space.
+    pub(crate) fn normal_at(
+        &self,
+        obj_point: Point<spaces::Object>,
+        obj_normal: Vector<spaces::Object>,
+    ) -> Vector<spaces::Object> {
+        match self.normal_modifier {
+            Some(ref m) => m.perturb(obj_point, obj_normal),
+            None => obj_normal,
+        }
+    }
+
+    /// Get the color of the light passing through this material at the given point.  This is
+    /// black for an opaque material.
+    pub(crate) fn transmission(&self, obj_point: Point<spaces::Object>) -> Color {
+        if self.transparency == 0.0 {
+            return Color::black();
+        }
+        self.pattern.color_at(obj_point) * self.transparency
+    }
+
     fn reflected_color(
         &self,
         world: &World,
@@ -88,6 +132,7 @@ impl Material {
         world.color_at(&refl_ray, total_contribution * self.reflectivity, debug) * self.reflectivity
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn refracted_color(
         &self,
         world: &World,
@@ -99,17 +144,10 @@ impl Material {
         total_contribution: f64,
         debug: bool,
     ) -> Color {
-        let n_ratio = n1 / n2;
-        let cos_i = eyev.dot(normalv);
-        let sin2_t = n_ratio * n_ratio * (1.0 - cos_i * cos_i);
-
-        if sin2_t > 1.0 {
+        let Some(direction) = Self::refracted_direction(n1, n2, eyev, normalv) else {
             // total internal reflection
             return Color::black();
-        }
-
-        let cos_t = (1.0 - sin2_t).sqrt();
-        let direction = normalv * (n_ratio * cos_i - cos_t) - eyev * n_ratio;
+        };
         // move 0.01 along the direction to escape the object is situated
         let refract_ray = Ray::new(point + direction * 0.01, direction);
 
@@ -120,8 +158,28 @@ impl Material {
             * self.transparency
     }
 
+    /// Calculate the direction of light refracted from the eye vector into a surface with the
+    /// given normal, or None when there is total internal reflection.
+    pub(crate) fn refracted_direction(
+        n1: f64,
+        n2: f64,
+        eyev: Vector<spaces::World>,
+        normalv: Vector<spaces::World>,
+    ) -> Option<Vector<spaces::World>> {
+        let n_ratio = n1 / n2;
+        let cos_i = eyev.dot(normalv);
+        let sin2_t = n_ratio * n_ratio * (1.0 - cos_i * cos_i);
+
+        if sin2_t > 1.0 {
+            return None;
+        }
+
+        let cos_t = (1.0 - sin2_t).sqrt();
+        Some(normalv * (n_ratio * cos_i - cos_t) - eyev * n_ratio)
+    }
+
     /// Calculate the reflectance using the schlick method
-    fn reflectance(
+    pub(crate) fn reflectance(
         n1: f64,
         n2: f64,
         eyev: Vector<spaces::World>,
@@ -148,29 +206,22 @@ impl Material {
         r0 + (1.0 - r0) * (1.0 - cos).powf(5.0)
     }
 
-    pub(crate) fn color_at(
+    /// Calculate the diffuse and specular contribution of a single light to the color of a
+    /// point with the given material color.
+    fn lighting(
         &self,
-        world: &World,
-        from_material: Option<&Material>,
-        ray: &Ray<spaces::World>,
-        world_point: Point<spaces::World>,
-        obj_point: Point<spaces::Object>,
+        material_color: Color,
+        light_at: LightAt,
         eyev: Vector<spaces::World>,
         normalv: Vector<spaces::World>,
-        total_contribution: f64,
-        debug: bool,
     ) -> Color {
-        let material_color = self.pattern.color_at(obj_point);
-
-        let light_at = world.light_at(world_point);
         // combine material color and light color
         let eff_color = material_color * light_at.intensity;
 
-        // compute the ambient contribution
-        let mut color = eff_color * self.ambient;
+        let mut color = Color::black();
 
-        // diffuse and specular only appear if not in shadow
-        if !light_at.in_shadow {
+        // diffuse and specular only appear where the light is visible
+        if light_at.visibility != Color::black() {
             // light_dot_normal is the cosine of the angle between the light vector and the normal
             // vector.  A negative number means it is on the other side of the surface.
             let light_dot_normal = light_at.direction.dot(normalv);
@@ -178,20 +229,58 @@ impl Material {
             // calculate diffuse and specular
             if light_dot_normal > 0.0 {
                 // compute the diffuse contribution
-                color += eff_color * self.diffuse * light_dot_normal;
+                color += eff_color * self.diffuse * light_dot_normal * light_at.visibility;
 
                 // reflect_dot_eye is the cosine of the angle between the reflection vector and the eye
                 // vector.
                 let reflect_dot_eye = (-light_at.direction).reflect(normalv).dot(eyev);
                 if reflect_dot_eye > 0.0 {
                     let factor = reflect_dot_eye.powf(self.shininess);
-                    color += light_at.intensity * self.specular * factor;
+                    color += light_at.intensity * self.specular * factor * light_at.visibility;
                 }
             }
         }
 
+        color
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn color_at(
+        &self,
+        world: &World,
+        from_material: Option<&Material>,
+        to_material: Option<&Material>,
+        ray: &Ray<spaces::World>,
+        world_point: Point<spaces::World>,
+        obj_point: Point<spaces::Object>,
+        eyev: Vector<spaces::World>,
+        normalv: Vector<spaces::World>,
+        total_contribution: f64,
+        debug: bool,
+    ) -> Color {
+        let material_color = self.pattern.color_at(obj_point);
+
+        // sum the emitted light and the diffuse and specular contributions of each light
+        let mut color = self.emission;
+        for light_at in world.lights_at(world_point) {
+            color += self.lighting(material_color, light_at, eyev, normalv);
+        }
+
+        // ambient light stands in for light scattered around the scene, so it is the same
+        // everywhere: it comes from the strongest light, without attenuation or a spot light's
+        // cone, rather than adding up with each light
+        let ambient = world.lights.iter().fold(Color::black(), |acc, light| {
+            let i = light.intensity;
+            Color::new(
+                acc.red.max(i.red),
+                acc.green.max(i.green),
+                acc.blue.max(i.blue),
+            )
+        });
+        color += material_color * ambient * self.ambient;
+
         let n1 = from_material.map(|m| m.refractive_index).unwrap_or(1.0);
-        let n2 = self.refractive_index;
+        let n2 = to_material.map(|m| m.refractive_index).unwrap_or(1.0);
 
         // add reflected color
         let reflected = if self.reflectivity > 0.0 {
@@ -253,6 +342,7 @@ mod test {
             m.color_at(
                 &w,
                 None,
+                None,
                 &ray,
                 position,
                 position.as_space(),
@@ -277,6 +367,7 @@ mod test {
             m.color_at(
                 &w,
                 None,
+                None,
                 &ray,
                 position,
                 position.as_space(),
@@ -301,6 +392,7 @@ mod test {
             m.color_at(
                 &w,
                 None,
+                None,
                 &ray,
                 position,
                 position.as_space(),
@@ -325,6 +417,7 @@ mod test {
             m.color_at(
                 &w,
                 None,
+                None,
                 &ray,
                 position,
                 position.as_space(),
@@ -349,6 +442,7 @@ mod test {
             m.color_at(
                 &w,
                 None,
+                None,
                 &ray,
                 position,
                 position.as_space(),
@@ -378,6 +472,7 @@ mod test {
             m.color_at(
                 &w,
                 None,
+                None,
                 &ray,
                 position,
                 position.as_space(),
@@ -390,6 +485,157 @@ mod test {
         );
     }
 
+    #[test]
+    fn emission() {
+        let position = Point::new(0, 0, 0);
+        let eyev = Vector::new(0, 0, -1);
+        let ray = Ray::new(position, -eyev);
+        let normalv = Vector::new(0, 0, -1);
+        // the light is behind the surface
+        let w = World::new(Light::new_point(Point::new(0, 0, 10), Color::white()));
+        let m = Material::default().with_emission(Color::new(1, 0.5, 0));
+        assert_relative_eq!(
+            m.color_at(
+                &w,
+                None,
+                None,
+                &ray,
+                position,
+                position.as_space(),
+                eyev,
+                normalv,
+                1.0,
+                false
+            ),
+            Color::new(1.1, 0.6, 0.1),
+        );
+    }
+
+    #[test]
+    fn lighting_scaled_by_visibility() {
+        let m = Material::default()
+            .with_ambient(0.1)
+            .with_diffuse(0.9)
+            .with_specular(0.0);
+        for (visibility, expected) in [(1.0, 0.9), (0.5, 0.45), (0.0, 0.0)] {
+            let light_at = LightAt {
+                intensity: Color::white(),
+                direction: Vector::new(0, 0, -1),
+                visibility: Color::white() * visibility,
+            };
+            assert_relative_eq!(
+                m.lighting(
+                    Color::white(),
+                    light_at,
+                    Vector::new(0, 0, -1),
+                    Vector::new(0, 0, -1)
+                ),
+                Color::new(expected, expected, expected)
+            );
+        }
+    }
+
+    #[test]
+    fn lights_are_summed() {
+        let position = Point::new(0, 0, 0);
+        let eyev = Vector::new(0, 0, -1);
+        let ray = Ray::new(position, -eyev);
+        let normalv = Vector::new(0, 0, -1);
+        // one light in front of the surface, and a dimmer one behind it, which adds no more
+        // ambient light
+        let mut w = World::new(Light::new_point(Point::new(0, 0, -10), Color::white()));
+        w.add_light(Light::new_point(Point::new(0, 0, 10), Color::white() * 0.5));
+        let m = Material::default();
+        assert_relative_eq!(
+            m.color_at(
+                &w,
+                None,
+                None,
+                &ray,
+                position,
+                position.as_space(),
+                eyev,
+                normalv,
+                1.0,
+                false
+            ),
+            Color::new(1.9, 1.9, 1.9)
+        );
+    }
+
+    #[test]
+    fn ambient_from_strongest_light() {
+        let position = Point::new(0, 0, 0);
+        let eyev = Vector::new(0, 0, -1);
+        let ray = Ray::new(position, -eyev);
+        let normalv = Vector::new(0, 0, -1);
+        // only ambient light reaches a surface facing away from every light
+        let mut w = World::new(Light::new_point(
+            Point::new(0, 0, 10),
+            Color::new(1, 0.5, 0.5),
+        ));
+        w.add_light(Light::new_point(
+            Point::new(0, 5, 10),
+            Color::new(0.5, 0.5, 2),
+        ));
+        w.add_light(Light::new_point(
+            Point::new(0, -5, 10),
+            Color::white() * 0.5,
+        ));
+        let m = Material::default();
+        assert_relative_eq!(
+            m.color_at(
+                &w,
+                None,
+                None,
+                &ray,
+                position,
+                position.as_space(),
+                eyev,
+                normalv,
+                1.0,
+                false
+            ),
+            Color::new(0.1, 0.05, 0.2)
+        );
+    }
+
+    #[test]
+    fn ambient_outside_spot_cone() {
+        // a spot light shining away from the point, attenuated by distance, still gives the
+        // same ambient light as an unattenuated point light
+        let position = Point::new(0, 0, 0);
+        let eyev = Vector::new(0, 0, -1);
+        let ray = Ray::new(position, -eyev);
+        let normalv = Vector::new(0, 0, -1);
+        let w = World::new(
+            Light::new_spot(
+                Point::new(0, 0, -10),
+                Vector::new(0, 0, -1),
+                0.1,
+                0.2,
+                Color::white(),
+            )
+            .with_attenuation(Attenuation::InverseSquare),
+        );
+        let m = Material::default();
+        assert_relative_eq!(
+            m.color_at(
+                &w,
+                None,
+                None,
+                &ray,
+                position,
+                position.as_space(),
+                eyev,
+                normalv,
+                1.0,
+                false
+            ),
+            Color::new(0.1, 0.1, 0.1)
+        );
+    }
+
     #[test]
     fn no_reflection() {
         let mut w = World::default();
@@ -466,9 +712,9 @@ mod test {
         let ray = Ray::new(eye, Vector::new(0, 0, 1));
         let mut inters = Intersections::default();
         obj.intersect(ObjectIndex::test_value(0), &ray, &mut inters);
-        let (_, t, _) = inters.hit();
+        let (_, hit, _) = inters.hit();
 
-        let point = ray.position(t.unwrap());
+        let point = ray.position(hit.unwrap().t);
         let eyev = (eye - point).normalize();
         let normalv = obj.normal(point);
         let n1 = 1.0;
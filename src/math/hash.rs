@@ -0,0 +1,22 @@
+/// Scramble the bits of `h` with the finalizer of the splitmix64 generator, so that similar
+/// inputs give unrelated outputs.
+pub(crate) fn splitmix64(mut h: u64) -> u64 {
+    h ^= h >> 30;
+    h = h.wrapping_mul(0xbf58476d1ce4e5b9);
+    h ^= h >> 27;
+    h = h.wrapping_mul(0x94d049bb133111eb);
+    h ^ (h >> 31)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn splitmix64_sequence() {
+        // the first outputs of splitmix64 seeded with 0
+        let gamma = 0x9e3779b97f4a7c15u64;
+        assert_eq!(splitmix64(gamma), 0xe220a8397b1dcdaf);
+        assert_eq!(splitmix64(gamma.wrapping_mul(2)), 0x6e789e6aa1b965f4);
+    }
+}
@@ -62,6 +62,11 @@ impl<const N: usize, S1: Space, S2: Space> Mat<N, S1, S2> {
         res
     }
 
+    /// Reinterpret this matrix as a transformation between other spaces.
+    pub fn as_spaces<T1: Space, T2: Space>(&self) -> Mat<N, T1, T2> {
+        Mat(self.0, PhantomData)
+    }
+
     pub fn transpose(&self) -> Mat<N, S2, S1> {
         let mut res = Mat::default();
         for i in 0..N {
@@ -184,57 +189,57 @@ impl<S1: Space, S2: Space> Mat<4, S1, S2> {
     }
 
     pub fn translate<X: Into<f64>, Y: Into<f64>, Z: Into<f64>>(&self, x: X, y: Y, z: Z) -> Self {
-        return mat4![
+        mat4![
             1, 0, 0, x;
             0, 1, 0, y;
             0, 0, 1, z;
             0, 0, 0, 1;
-        ] * *self;
+        ] * *self
     }
 
     pub fn scale<X: Into<f64>, Y: Into<f64>, Z: Into<f64>>(&self, x: X, y: Y, z: Z) -> Self {
-        return mat4![
+        mat4![
             x, 0, 0, 0;
             0, y, 0, 0;
             0, 0, z, 0;
             0, 0, 0, 1;
-        ] * *self;
+        ] * *self
     }
 
     pub fn rotate_x<R: Into<f64>>(&self, r: R) -> Self {
         let r = r.into();
         let c = r.cos();
         let s = r.sin();
-        return mat4![
+        mat4![
             1, 0,  0, 0;
             0, c, -s, 0;
             0, s,  c, 0;
             0, 0,  0, 1;
-        ] * *self;
+        ] * *self
     }
 
     pub fn rotate_y<R: Into<f64>>(&self, r: R) -> Self {
         let r = r.into();
         let c = r.cos();
         let s = r.sin();
-        return mat4![
+        mat4![
             c,  0, s, 0;
             0,  1, 0, 0;
             -s, 0, c, 0;
             0,  0, 0, 1;
-        ] * *self;
+        ] * *self
     }
 
     pub fn rotate_z<R: Into<f64>>(&self, r: R) -> Self {
         let r = r.into();
         let c = r.cos();
         let s = r.sin();
-        return mat4![
+        mat4![
             c, -s, 0, 0;
             s,  c, 0, 0;
             0,  0, 1, 0;
             0,  0, 0, 1;
-        ] * *self;
+        ] * *self
     }
 
     pub fn shear<
@@ -253,12 +258,12 @@ impl<S1: Space, S2: Space> Mat<4, S1, S2> {
         zx: ZX,
         zy: ZY,
     ) -> Self {
-        return mat4![
+        mat4![
             1, xy,  xz, 0;
             yx, 1,  yz, 0;
             zx, zy, 1,  0;
             0,  0,  0,  1;
-        ] * *self;
+        ] * *self
     }
 }
 
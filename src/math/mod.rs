@@ -1,8 +1,10 @@
+mod hash;
 mod matrices;
 mod points;
 mod space;
 mod vectors;
 
+pub(crate) use hash::*;
 pub use matrices::*;
 pub use points::*;
 pub use space::*;
@@ -0,0 +1,185 @@
+use crate::{Point, Space};
+
+/// Ken Perlin's reference permutation of 0..256.
+#[rustfmt::skip]
+const PERMUTATION: [u8; 256] = [
+    151, 160, 137, 91, 90, 15, 131, 13, 201, 95, 96, 53, 194, 233, 7, 225, 140, 36, 103, 30, 69,
+    142, 8, 99, 37, 240, 21, 10, 23, 190, 6, 148, 247, 120, 234, 75, 0, 26, 197, 62, 94, 252,
+    219, 203, 117, 35, 11, 32, 57, 177, 33, 88, 237, 149, 56, 87, 174, 20, 125, 136, 171, 168,
+    68, 175, 74, 165, 71, 134, 139, 48, 27, 166, 77, 146, 158, 231, 83, 111, 229, 122, 60, 211,
+    133, 230, 220, 105, 92, 41, 55, 46, 245, 40, 244, 102, 143, 54, 65, 25, 63, 161, 1, 216, 80,
+    73, 209, 76, 132, 187, 208, 89, 18, 169, 200, 196, 135, 130, 116, 188, 159, 86, 164, 100,
+    109, 198, 173, 186, 3, 64, 52, 217, 226, 250, 124, 123, 5, 202, 38, 147, 118, 126, 255, 82,
+    85, 212, 207, 206, 59, 227, 47, 16, 58, 17, 182, 189, 28, 42, 223, 183, 170, 213, 119, 248,
+    152, 2, 44, 154, 163, 70, 221, 153, 101, 155, 167, 43, 172, 9, 129, 22, 39, 253, 19, 98, 108,
+    110, 79, 113, 224, 232, 178, 185, 112, 104, 218, 246, 97, 228, 251, 34, 242, 193, 238, 210,
+    144, 12, 191, 179, 162, 241, 81, 51, 145, 235, 249, 14, 239, 107, 49, 192, 214, 31, 181, 199,
+    106, 157, 184, 84, 204, 176, 115, 121, 50, 45, 127, 4, 150, 254, 138, 236, 205, 93, 222, 114,
+    67, 29, 24, 72, 243, 141, 128, 195, 78, 66, 215, 61, 156, 180,
+];
+
+fn perm(i: usize) -> usize {
+    PERMUTATION[i & 255] as usize
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+/// The dot product of (x, y, z) with one of 12 gradient directions chosen by the hash.
+fn grad(hash: usize, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+/// Calculate Perlin's "improved noise" at the given point: a smooth pseudo-random value,
+/// roughly in the range [-1, 1], which is zero at every point with integer coordinates.
+pub fn noise<S: Space>(p: Point<S>) -> f64 {
+    let (xf, yf, zf) = (p.x.floor(), p.y.floor(), p.z.floor());
+    // the lattice cell containing the point, and the position within it
+    let (xi, yi, zi) = (
+        (xf as i64 & 255) as usize,
+        (yf as i64 & 255) as usize,
+        (zf as i64 & 255) as usize,
+    );
+    let (x, y, z) = (p.x - xf, p.y - yf, p.z - zf);
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+
+    let a = perm(xi) + yi;
+    let aa = perm(a) + zi;
+    let ab = perm(a + 1) + zi;
+    let b = perm(xi + 1) + yi;
+    let ba = perm(b) + zi;
+    let bb = perm(b + 1) + zi;
+
+    lerp(
+        w,
+        lerp(
+            v,
+            lerp(u, grad(perm(aa), x, y, z), grad(perm(ba), x - 1.0, y, z)),
+            lerp(
+                u,
+                grad(perm(ab), x, y - 1.0, z),
+                grad(perm(bb), x - 1.0, y - 1.0, z),
+            ),
+        ),
+        lerp(
+            v,
+            lerp(
+                u,
+                grad(perm(aa + 1), x, y, z - 1.0),
+                grad(perm(ba + 1), x - 1.0, y, z - 1.0),
+            ),
+            lerp(
+                u,
+                grad(perm(ab + 1), x, y - 1.0, z - 1.0),
+                grad(perm(bb + 1), x - 1.0, y - 1.0, z - 1.0),
+            ),
+        ),
+    )
+}
+
+/// Sum `octaves` layers of noise, each at twice the frequency and half the amplitude of the
+/// last, for detail at many scales.  The result is roughly in the range [-1, 1].
+pub fn fbm<S: Space>(p: Point<S>, octaves: u32) -> f64 {
+    octave_sum(p, octaves, noise)
+}
+
+/// Like `fbm`, but summing the absolute value of each layer, giving sharp creases where the
+/// noise crosses zero.  The result is in the range [0, 1].
+pub fn turbulence<S: Space>(p: Point<S>, octaves: u32) -> f64 {
+    octave_sum(p, octaves, |p| noise(p).abs())
+}
+
+fn octave_sum<S: Space>(p: Point<S>, octaves: u32, f: impl Fn(Point<S>) -> f64) -> f64 {
+    let mut sum = 0.0;
+    let mut total_amplitude = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    for _ in 0..octaves {
+        sum += amplitude
+            * f(Point::new(
+                p.x * frequency,
+                p.y * frequency,
+                p.z * frequency,
+            ));
+        total_amplitude += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    if total_amplitude > 0.0 {
+        sum / total_amplitude
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::spaces;
+    use approx::*;
+
+    type P = Point<spaces::Pattern>;
+
+    #[test]
+    fn permutation() {
+        let mut sorted = PERMUTATION;
+        sorted.sort();
+        assert!(sorted.iter().enumerate().all(|(i, &v)| i == v as usize));
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn reference_value() {
+        // the value printed by Perlin's reference implementation
+        assert_relative_eq!(noise(P::new(3.14, 42, 7)), 0.13691995878400012);
+    }
+
+    #[test]
+    fn zero_on_lattice() {
+        for p in [P::new(0, 0, 0), P::new(1, 2, 3), P::new(-5, 7, -300)] {
+            assert_relative_eq!(noise(p), 0.0);
+        }
+    }
+
+    #[test]
+    fn smooth_and_bounded() {
+        for i in 0..1000 {
+            let t = i as f64 * 0.0137;
+            let p = P::new(t, t * 0.7 - 3.0, 11.0 - t * 1.3);
+            let n = noise(p);
+            assert!((-1.0..=1.0).contains(&n));
+            let nearby = noise(P::new(p.x + 0.001, p.y, p.z));
+            assert!((n - nearby).abs() < 0.01);
+            let f = fbm(p, 4);
+            assert!((-1.0..=1.0).contains(&f));
+            let t = turbulence(p, 4);
+            assert!((0.0..=1.0).contains(&t));
+        }
+    }
+
+    #[test]
+    fn octaves() {
+        let p = P::new(0.3, 0.6, 0.9);
+        assert_relative_eq!(fbm(p, 1), noise(p));
+        assert_relative_eq!(turbulence(p, 1), noise(p).abs());
+        assert_relative_eq!(
+            fbm(p, 2),
+            (noise(p) + 0.5 * noise(P::new(0.6, 1.2, 1.8))) / 1.5
+        );
+        assert_relative_eq!(fbm(p, 0), 0.0);
+    }
+}
@@ -0,0 +1,301 @@
+use crate::noise::fbm;
+use crate::{spaces, ImageTexture, Mat, Pattern, Point, UvMapping, Vector};
+use std::f64::consts::PI;
+
+/// The number of octaves of noise used by noise bumps
+const NOISE_OCTAVES: u32 = 4;
+
+/// The distance, in object space, over which heights and (u, v) coordinates are differentiated
+const EPSILON: f64 = 1e-4;
+
+/// NormalModifier perturbs the normals of a material, so that a surface appears bumpy without
+/// any extra geometry.
+#[derive(Debug, Clone)]
+pub struct NormalModifier {
+    transform: Mat<4, spaces::Object, spaces::Pattern>,
+    modifier_impl: ModifierImpl,
+}
+
+#[derive(Debug, Clone)]
+enum ModifierImpl {
+    /// Bumps of a height which is a function of the point
+    Bump(Height),
+    /// A tangent-space normal map, and the mapping with which to apply it
+    NormalMap(ImageTexture, UvMapping),
+}
+
+/// A height field, with its amplitude.
+#[derive(Debug, Clone)]
+enum Height {
+    Waves(f64),
+    Ripples(f64),
+    Noise(f64),
+    Emboss(Box<Pattern>, f64),
+}
+
+use ModifierImpl::*;
+
+impl NormalModifier {
+    fn new(modifier_impl: ModifierImpl) -> Self {
+        Self {
+            transform: Mat::identity(),
+            modifier_impl,
+        }
+    }
+
+    /// Create sine waves running along the x axis, with a wavelength of one unit and the
+    /// given amplitude.
+    pub fn waves(amplitude: f64) -> Self {
+        Self::new(Bump(Height::Waves(amplitude)))
+    }
+
+    /// Create circular sine waves around the y axis, with a wavelength of one unit and the
+    /// given amplitude.
+    pub fn ripples(amplitude: f64) -> Self {
+        Self::new(Bump(Height::Ripples(amplitude)))
+    }
+
+    /// Create irregular bumps from noise, up to about the given amplitude.
+    pub fn noise(amplitude: f64) -> Self {
+        Self::new(Bump(Height::Noise(amplitude)))
+    }
+
+    /// Create bumps from a pattern, where white is raised by the given amplitude above black.
+    pub fn emboss(pattern: impl Into<Pattern>, amplitude: f64) -> Self {
+        Self::new(Bump(Height::Emboss(Box::new(pattern.into()), amplitude)))
+    }
+
+    /// Create a tangent-space normal map, wrapped around an object with the given mapping.
+    /// Red, green and blue from 0 to 1 give the components of the normal from -1 to 1, in the
+    /// directions of increasing u, increasing v, and the surface normal.
+    pub fn normal_map(texture: ImageTexture, mapping: UvMapping) -> Self {
+        Self::new(NormalMap(texture, mapping))
+    }
+
+    /// Return an updated object with the given transform, where the transform is
+    /// from pattern space to object space.
+    pub fn with_transform(
+        mut self,
+        obj_to_pattern: Mat<4, spaces::Pattern, spaces::Object>,
+    ) -> Self {
+        self.transform = obj_to_pattern.inverse();
+        self
+    }
+
+    /// Perturb the given normal at the given point, both in object space.
+    pub(crate) fn perturb(
+        &self,
+        obj_p: Point<spaces::Object>,
+        normal: Vector<spaces::Object>,
+    ) -> Vector<spaces::Object> {
+        let normal = normal.normalize();
+        match self.modifier_impl {
+            Bump(ref height) => {
+                // tilt the normal away from the slope of the height field
+                let slope = gradient(obj_p, false, |p| height.at(self.transform * p, p));
+                (normal - tangential(slope, normal)).normalize()
+            }
+            NormalMap(ref texture, mapping) => {
+                let uv = |p| mapping.map(self.transform * p);
+                let du = tangential(gradient(obj_p, true, |p| uv(p).0), normal);
+                let dv = tangential(gradient(obj_p, true, |p| uv(p).1), normal);
+                if du.magnitude() == 0.0 || dv.magnitude() == 0.0 {
+                    // the mapping is degenerate here, such as at the pole of a sphere
+                    return normal;
+                }
+                let (u, v) = uv(obj_p);
+                let c = texture.color_at(u, v);
+                let n = (du.normalize() * (c.red * 2.0 - 1.0))
+                    + (dv.normalize() * (c.green * 2.0 - 1.0))
+                    + (normal * (c.blue * 2.0 - 1.0));
+                n.normalize()
+            }
+        }
+    }
+}
+
+impl Height {
+    /// Calculate the height at the given point, in pattern and object space.
+    fn at(&self, p: Point<spaces::Pattern>, obj_p: Point<spaces::Object>) -> f64 {
+        match *self {
+            Height::Waves(amplitude) => amplitude * (2.0 * PI * p.x).sin(),
+            Height::Ripples(amplitude) => {
+                amplitude * (2.0 * PI * (p.x * p.x + p.z * p.z).sqrt()).sin()
+            }
+            Height::Noise(amplitude) => amplitude * fbm(p, NOISE_OCTAVES),
+            Height::Emboss(ref pattern, amplitude) => {
+                let c = pattern.color_at(obj_p);
+                amplitude * (c.red + c.green + c.blue) / 3.0
+            }
+        }
+    }
+}
+
+/// Calculate the gradient of a function at the given point by central differences.  If `wrap`
+/// is true, values are assumed to wrap around from 1 to 0, as (u, v) coordinates do, if they
+/// change by more than one half.
+fn gradient(
+    obj_p: Point<spaces::Object>,
+    wrap: bool,
+    f: impl Fn(Point<spaces::Object>) -> f64,
+) -> Vector<spaces::Object> {
+    let d = |offset: Vector<spaces::Object>| {
+        let diff = f(obj_p + offset) - f(obj_p - offset);
+        let diff = if !wrap {
+            diff
+        } else if diff > 0.5 {
+            diff - 1.0
+        } else if diff < -0.5 {
+            diff + 1.0
+        } else {
+            diff
+        };
+        diff / (2.0 * EPSILON)
+    };
+    Vector::new(
+        d(Vector::new(EPSILON, 0, 0)),
+        d(Vector::new(0, EPSILON, 0)),
+        d(Vector::new(0, 0, EPSILON)),
+    )
+}
+
+/// Get the part of a vector perpendicular to the given unit normal.
+fn tangential(v: Vector<spaces::Object>, normal: Vector<spaces::Object>) -> Vector<spaces::Object> {
+    v - normal * v.dot(normal)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Color, Framebuffer};
+    use approx::*;
+    use std::sync::Arc;
+
+    fn up() -> Vector<spaces::Object> {
+        Vector::new(0, 1, 0)
+    }
+
+    #[test]
+    fn waves() {
+        let m = NormalModifier::waves(0.1);
+        // the slope of the waves at x = 0 is 2 pi times the amplitude
+        assert_relative_eq!(
+            m.perturb(Point::new(0, 0, 0), up()),
+            Vector::new(-0.2 * PI, 1, 0).normalize(),
+            epsilon = 1e-6
+        );
+        // flat at the crests
+        assert_relative_eq!(
+            m.perturb(Point::new(0.25, 0, 3), up()),
+            up(),
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn waves_with_transform() {
+        let m = NormalModifier::waves(0.1).with_transform(Mat::identity().scale(2, 2, 2));
+        assert_relative_eq!(
+            m.perturb(Point::new(0, 0, 0), up()),
+            Vector::new(-0.1 * PI, 1, 0).normalize(),
+            epsilon = 1e-6
+        );
+        assert_relative_eq!(m.perturb(Point::new(0.5, 0, 0), up()), up(), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn ripples() {
+        let m = NormalModifier::ripples(0.1);
+        // descending away from the y axis at a radius of one half
+        assert_relative_eq!(
+            m.perturb(Point::new(0, 0, 0.5), up()),
+            Vector::new(0, 1, 0.2 * PI).normalize(),
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn noise() {
+        let m = NormalModifier::noise(0.2);
+        let mut tilted = 0;
+        for i in 0..20 {
+            let n = m.perturb(Point::new(i as f64 * 0.37, 0, i as f64 * 0.11), up() * 2.0);
+            assert_relative_eq!(n.magnitude(), 1.0);
+            assert!(n.y > 0.0);
+            if n.y < 0.999 {
+                tilted += 1;
+            }
+        }
+        assert!(tilted > 10);
+    }
+
+    #[test]
+    fn emboss() {
+        let m = NormalModifier::emboss(Pattern::gradient(Color::black(), Color::white()), 0.5);
+        assert_relative_eq!(
+            m.perturb(Point::new(0.5, 0, 0), up()),
+            Vector::new(-0.5, 1, 0).normalize(),
+            epsilon = 1e-6
+        );
+        assert_relative_eq!(m.perturb(Point::new(2, 0, 0), up()), up());
+    }
+
+    #[test]
+    fn emboss_edge() {
+        // black rises to white across the edge at x = 1, however high the step
+        let m = NormalModifier::emboss(Pattern::checker(Color::black(), Color::white()), 1.0);
+        let n = m.perturb(Point::new(1, 0.5, 0.5), up());
+        assert!(n.x < -0.99);
+        assert!(n.y > 0.0);
+    }
+
+    fn flat_texture(c: Color) -> ImageTexture {
+        let mut fb = Framebuffer::new(1, 1);
+        fb.set(0, 0, c);
+        ImageTexture::new(Arc::new(fb))
+    }
+
+    #[test]
+    fn normal_map() {
+        let p = Point::new(0.3, 0, 0.6);
+        let flat =
+            NormalModifier::normal_map(flat_texture(Color::new(0.5, 0.5, 1)), UvMapping::Planar);
+        assert_relative_eq!(flat.perturb(p, up()), up());
+
+        // with planar mapping, u increases with x and v with z
+        let m = NormalModifier::normal_map(flat_texture(Color::new(1, 0.5, 1)), UvMapping::Planar);
+        assert_relative_eq!(
+            m.perturb(p, up()),
+            Vector::new(1, 1, 0).normalize(),
+            epsilon = 1e-6
+        );
+        let m = NormalModifier::normal_map(flat_texture(Color::new(0.5, 0, 1)), UvMapping::Planar);
+        assert_relative_eq!(
+            m.perturb(p, up()),
+            Vector::new(0, 1, -1).normalize(),
+            epsilon = 1e-6
+        );
+        // across the seam where u wraps from 1 to 0
+        let m = NormalModifier::normal_map(flat_texture(Color::new(1, 0.5, 1)), UvMapping::Planar);
+        assert_relative_eq!(
+            m.perturb(Point::new(1, 0, 0.6), up()),
+            Vector::new(1, 1, 0).normalize(),
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn normal_map_sphere() {
+        let m =
+            NormalModifier::normal_map(flat_texture(Color::new(0.5, 1, 1)), UvMapping::Spherical);
+        // at the front of the sphere, v increases upward
+        let n = Vector::new(0, 0, -1);
+        assert_relative_eq!(
+            m.perturb(Point::new(0, 0, -1), n),
+            Vector::new(0, 1, -1).normalize(),
+            epsilon = 1e-6
+        );
+        // at the poles, the normal is unchanged
+        assert_relative_eq!(m.perturb(Point::new(0, 1, 0), up()), up());
+    }
+}
@@ -0,0 +1,496 @@
+use crate::math::splitmix64;
+use crate::{spaces, Color, Material, ObjectIndex, Point, Ray, RayColor, Vector, World};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+use std::f64::consts::PI;
+
+/// The number of bounces before paths may be ended by Russian roulette
+const MIN_BOUNCES: u32 = 3;
+
+/// The maximum number of bounces, to end paths trapped between mirrors
+const MAX_BOUNCES: u32 = 100;
+
+/// PathTracer renders a world by Monte Carlo path tracing, following random paths of light
+/// between surfaces to include indirect light and the light of emissive materials.
+///
+/// Materials are interpreted physically: a surface reflects light as a mirror in proportion to
+/// its `reflective` value, transmits light in proportion to its `transparency` (split between
+/// reflection and refraction by the Fresnel effect), and reflects the rest diffusely, tinted by
+/// its color and `diffuse` value.  Ambient light and specular highlights are not used.  Lights
+/// illuminate surfaces as in the Phong model, so scenes are about as bright as when rendered
+/// with `World` itself.
+///
+/// Emissive materials light the objects around them.  Light from spheres, cubes, triangles (such
+/// as those of meshes) and finite cylinders is sampled directly, by casting shadow rays to
+/// random points on their surfaces, as for lights.  Other shapes only light the surfaces from
+/// which random paths happen to reach them, which is much noisier.
+///
+/// Each camera ray seeds its own random number generator, so renders are reproducible for a
+/// given seed, however they are divided among threads.
+pub struct PathTracer<'a> {
+    world: &'a World,
+    seed: u64,
+    samples: u32,
+
+    /// The emissive objects which are sampled directly, in order of index
+    emitters: Vec<Emitter>,
+
+    /// The total power of `emitters`
+    total_power: f64,
+}
+
+/// An emissive object which is sampled as a light.
+#[derive(Debug)]
+struct Emitter {
+    index: ObjectIndex,
+
+    /// The power of the light emitted by this object, as its area times its brightness
+    power: f64,
+
+    /// The total power of this and the preceding emitters, for choosing among them
+    cumulative_power: f64,
+}
+
+impl<'a> PathTracer<'a> {
+    pub fn new(world: &'a World) -> Self {
+        let mut total_power = 0.0;
+        let emitters = world
+            .emitters()
+            .into_iter()
+            .filter_map(|index| {
+                let obj = &world[index];
+                let e = obj.material().emission;
+                let power = obj.surface_area()? * (e.red + e.green + e.blue) / 3.0;
+                if power <= 0.0 {
+                    return None;
+                }
+                total_power += power;
+                Some(Emitter {
+                    index,
+                    power,
+                    cumulative_power: total_power,
+                })
+            })
+            .collect();
+        Self {
+            world,
+            seed: 0,
+            samples: 1,
+            emitters,
+            total_power,
+        }
+    }
+
+    /// Return an updated path tracer using the given seed for its random numbers.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Return an updated path tracer which traces the given number of paths for each ray from
+    /// the camera.
+    pub fn with_samples(mut self, samples: u32) -> Self {
+        self.samples = samples.max(1);
+        self
+    }
+
+    /// Trace a single path backward from the eye along the given ray.
+    fn trace(&self, ray: &Ray<spaces::World>, rng: &mut impl Rng, debug: bool) -> Color {
+        let world = self.world;
+        let mut ray = *ray;
+        let mut color = Color::black();
+        // the fraction of light reaching the eye from the current point on the path
+        let mut throughput = Color::white();
+        // whether the last bounce was diffuse, in which case the light of the emitters has
+        // already been sampled directly and is not counted again
+        let mut diffused = false;
+
+        for bounce in 0..MAX_BOUNCES {
+            let mut inters = world.cast(&ray);
+            let (from_idx, Some(hit), to_idx) = inters.hit() else {
+                color += throughput * world.background.color_at(ray.direction);
+                break;
+            };
+            if debug {
+                dbg!((bounce, from_idx, hit, to_idx));
+            }
+            let obj = &world[hit.object_index];
+            let material = obj.material();
+            let (point, obj_point, normalv) = obj.surface_at(&hit, &ray);
+            let eyev = -ray.direction;
+            if !diffused || !self.is_emitter(hit.object_index) {
+                color += throughput * material.emission;
+            }
+
+            // choose whether the light was reflected, transmitted or diffused
+            let choice: f64 = rng.gen();
+            diffused = false;
+            let direction = if choice < material.reflectivity {
+                ray.direction.reflect(normalv)
+            } else if choice < material.reflectivity + material.transparency {
+                let n1 = from_idx.map_or(1.0, |i| world[i].material().refractive_index);
+                let n2 = to_idx.map_or(1.0, |i| world[i].material().refractive_index);
+                let reflectance = Material::reflectance(n1, n2, eyev, normalv);
+                match Material::refracted_direction(n1, n2, eyev, normalv) {
+                    Some(refracted) if rng.gen::<f64>() >= reflectance => refracted.normalize(),
+                    _ => ray.direction.reflect(normalv),
+                }
+            } else {
+                throughput = throughput * material.pattern.color_at(obj_point) * material.diffuse;
+                color += throughput
+                    * (self.direct_light(point, normalv, rng)
+                        + self.emitted_light(point, normalv, rng));
+                diffused = true;
+                cosine_sample(normalv, rng)
+            };
+
+            if bounce >= MIN_BOUNCES {
+                // end the path at random, weighting the paths which survive to compensate
+                let survival = throughput
+                    .red
+                    .max(throughput.green)
+                    .max(throughput.blue)
+                    .min(1.0);
+                if rng.gen::<f64>() >= survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+            if throughput == Color::black() {
+                break;
+            }
+
+            // move 0.01 along the direction to escape the surface
+            ray = Ray::new(point + direction * 0.01, direction);
+        }
+        color
+    }
+
+    /// Calculate the light arriving directly from the world's lights at a diffuse surface, by
+    /// casting a shadow ray to a random point on each light.
+    fn direct_light(
+        &self,
+        point: Point<spaces::World>,
+        normalv: Vector<spaces::World>,
+        rng: &mut impl Rng,
+    ) -> Color {
+        let mut color = Color::black();
+        for light in &self.world.lights {
+            let factor = light.intensity_factor(point);
+            let light_dot_normal = light.direction_from(point).dot(normalv);
+            if factor == 0.0 || light_dot_normal <= 0.0 {
+                continue;
+            }
+            let (to_light, distance) = light.shadow_ray(rng.gen_range(0..light.samples()), point);
+            color += light.intensity
+                * (factor * light_dot_normal)
+                * self.world.shadow_color(point, to_light, distance);
+        }
+        color
+    }
+
+    /// Calculate the light arriving directly from the emitters at a diffuse surface, by
+    /// choosing one in proportion to its power and casting a shadow ray to a random point on
+    /// its surface.
+    fn emitted_light(
+        &self,
+        point: Point<spaces::World>,
+        normalv: Vector<spaces::World>,
+        rng: &mut impl Rng,
+    ) -> Color {
+        if self.emitters.is_empty() {
+            return Color::black();
+        }
+        let choice = rng.gen::<f64>() * self.total_power;
+        let i = self
+            .emitters
+            .partition_point(|e| e.cumulative_power <= choice)
+            .min(self.emitters.len() - 1);
+        let emitter = &self.emitters[i];
+        let obj = &self.world[emitter.index];
+        let Some((light_point, light_normal, density)) = obj.sample_surface(rng.gen(), rng.gen())
+        else {
+            return Color::black();
+        };
+
+        let to_light = light_point - point;
+        let distance = to_light.magnitude();
+        let to_light = to_light / distance;
+        let cos_surface = to_light.dot(normalv);
+        // emissive surfaces emit from both sides
+        let cos_light = to_light.dot(light_normal).abs();
+        if !(cos_surface > 0.0 && cos_light > 0.0) {
+            return Color::black();
+        }
+        // stop short of the emitter's own surface
+        let visibility = self.world.shadow_color(point, to_light, distance - 0.02);
+
+        // the probability density of the direction to the light, per unit of solid angle
+        let density =
+            density * (emitter.power / self.total_power) * distance * distance / cos_light;
+        // a diffuse surface reflects 1 / pi of the light from each unit of solid angle
+        obj.material().emission * visibility * (cos_surface / (PI * density))
+    }
+
+    /// Determine whether the object with the given index is sampled as an emitter.
+    fn is_emitter(&self, index: ObjectIndex) -> bool {
+        self.emitters
+            .binary_search_by_key(&index.0, |e| e.index.0)
+            .is_ok()
+    }
+}
+
+impl RayColor for PathTracer<'_> {
+    fn color_at(&self, ray: &Ray<spaces::World>, debug: bool) -> Color {
+        let mut rng = Pcg64Mcg::seed_from_u64(ray_seed(self.seed, ray));
+        let mut color = Color::black();
+        for _ in 0..self.samples {
+            color += self.trace(ray, &mut rng, debug);
+        }
+        color / self.samples as f64
+    }
+}
+
+/// Choose a random direction in the hemisphere around the given normal, with a probability
+/// proportional to the cosine of its angle to the normal, as diffuse reflection is.
+fn cosine_sample(normalv: Vector<spaces::World>, rng: &mut impl Rng) -> Vector<spaces::World> {
+    // an orthonormal basis around the normal
+    let helper = if normalv.x.abs() > 0.9 {
+        Vector::new(0, 1, 0)
+    } else {
+        Vector::new(1, 0, 0)
+    };
+    let tangent = helper.cross(normalv).normalize();
+    let bitangent = normalv.cross(tangent);
+
+    let phi = 2.0 * PI * rng.gen::<f64>();
+    let r2: f64 = rng.gen();
+    let r = r2.sqrt();
+    (tangent * (r * phi.cos()) + bitangent * (r * phi.sin()) + normalv * (1.0 - r2).sqrt())
+        .normalize()
+}
+
+/// Derive a seed for the random numbers of the paths traced along a ray.
+fn ray_seed(seed: u64, ray: &Ray<spaces::World>) -> u64 {
+    [
+        ray.origin.x,
+        ray.origin.y,
+        ray.origin.z,
+        ray.direction.x,
+        ray.direction.y,
+        ray.direction.z,
+    ]
+    .iter()
+    .fold(seed, |h, v| {
+        splitmix64((h ^ v.to_bits()).wrapping_add(0x9e3779b97f4a7c15))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::csg::*;
+    use crate::*;
+    use approx::*;
+
+    /// A world with no light but the background.
+    fn dark_world(background: Color) -> World {
+        let mut w = World::new(Light::new_point(Point::new(0, 10, 0), Color::black()));
+        w.set_background(Background::Solid(background));
+        w
+    }
+
+    #[test]
+    fn background() {
+        let w = dark_world(Color::new(0.2, 0.4, 0.6));
+        let pt = PathTracer::new(&w);
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+        assert_relative_eq!(pt.color_at(&r, false), Color::new(0.2, 0.4, 0.6));
+    }
+
+    #[test]
+    fn diffuse_under_uniform_sky() {
+        // every bounce from a convex object escapes to the sky
+        let mut w = dark_world(Color::new(1, 0.5, 0.25));
+        w.add_object(Object::new(Sphere).with_material(Material::default().with_diffuse(0.5)));
+        let pt = PathTracer::new(&w).with_samples(10);
+        let r = Ray::new(Point::new(0.3, 0.2, -5), Vector::new(0, 0, 1));
+        assert_relative_eq!(
+            pt.color_at(&r, false),
+            Color::new(0.5, 0.25, 0.125),
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn direct_light() {
+        let mut w = World::new(Light::new_point(Point::new(0, 10, 0), Color::white()));
+        w.add_object(Object::new(Plane));
+        let pt = PathTracer::new(&w);
+        let r = Ray::new(Point::new(0, 1, 0), Vector::new(0, -1, 0));
+        assert_relative_eq!(pt.color_at(&r, false), Color::new(0.9, 0.9, 0.9));
+    }
+
+    #[test]
+    fn mirror() {
+        let mut w = dark_world(Color::black());
+        w.set_background(Background::Gradient {
+            horizon: Color::black(),
+            zenith: Color::new(0, 0, 1),
+        });
+        w.add_object(
+            Object::new(Plane).with_material(
+                Material::default()
+                    .with_color(Color::black())
+                    .with_reflectivity(1.0),
+            ),
+        );
+        let pt = PathTracer::new(&w);
+        let r = Ray::new(Point::new(0, 1, 0), Vector::new(0, -1, 0));
+        assert_relative_eq!(pt.color_at(&r, false), Color::new(0, 0, 1));
+    }
+
+    #[test]
+    fn emissive_enclosure() {
+        // inside a sphere emitting 0.5 with an albedo of 0.5, light converges to
+        // 0.5 + 0.25 + 0.125 + ... = 1
+        let mut w = dark_world(Color::black());
+        w.add_object(
+            Object::new(Sphere).with_material(
+                Material::default()
+                    .with_diffuse(0.5)
+                    .with_emission(Color::new(0.5, 0.5, 0.5)),
+            ),
+        );
+        let pt = PathTracer::new(&w).with_samples(4000);
+        let r = Ray::new(Point::new(0, 0, 0), Vector::new(0, 0, 1));
+        assert_relative_eq!(pt.color_at(&r, false), Color::white(), epsilon = 0.05);
+    }
+
+    /// A dark world with a white diffuse floor.
+    fn floor_world() -> World {
+        let mut w = dark_world(Color::black());
+        w.add_object(Object::new(Plane));
+        w
+    }
+
+    /// A black material emitting the given brightness.
+    fn emitter(brightness: f64) -> Material {
+        Material::default()
+            .with_color(Color::black())
+            .with_emission(Color::white() * brightness)
+    }
+
+    #[test]
+    fn emission_seen() {
+        let mut w = floor_world();
+        w.add_object(
+            Object::new(Sphere)
+                .with_transform(Mat::identity().translate(0, 5, 0))
+                .with_material(emitter(2.0)),
+        );
+        let pt = PathTracer::new(&w);
+        let r = Ray::new(Point::new(0, 5, -5), Vector::new(0, 0, 1));
+        assert_relative_eq!(pt.color_at(&r, false), Color::new(2, 2, 2));
+    }
+
+    #[test]
+    fn sphere_light() {
+        // a sphere of radius 1 at a distance of 5 fills (1 / 5)^2 of the floor's view, so the
+        // floor reflects 0.9 * 25 / 25
+        let mut w = floor_world();
+        w.add_object(
+            Object::new(Sphere)
+                .with_transform(Mat::identity().translate(0, 5, 0))
+                .with_material(emitter(25.0)),
+        );
+        let pt = PathTracer::new(&w).with_samples(2000);
+        let r = Ray::new(Point::new(0, 1, 0), Vector::new(0, -1, 0));
+        assert_relative_eq!(
+            pt.color_at(&r, false),
+            Color::new(0.9, 0.9, 0.9),
+            epsilon = 0.05
+        );
+    }
+
+    #[test]
+    fn mesh_light() {
+        // a square panel of two triangles, 2 by 2 at a height of 1, whose form factor from the
+        // point below its center is 0.5541
+        let mut w = floor_world();
+        let p = |x, z| Point::new(x, 1, z);
+        w.add_object(
+            Object::group(
+                Group::new()
+                    .with_child(Object::new(Triangle::new(p(-1, -1), p(1, -1), p(1, 1))))
+                    .with_child(Object::new(Triangle::new(p(-1, -1), p(1, 1), p(-1, 1)))),
+            )
+            .with_material(emitter(2.0)),
+        );
+        assert_eq!(PathTracer::new(&w).emitters.len(), 2);
+        let pt = PathTracer::new(&w).with_samples(2000);
+        let r = Ray::new(Point::new(0, 0.5, 0), Vector::new(0, -1, 0));
+        let expected = 0.9 * 2.0 * 0.5541;
+        assert_relative_eq!(
+            pt.color_at(&r, false),
+            Color::new(expected, expected, expected),
+            epsilon = 0.05
+        );
+    }
+
+    #[test]
+    fn color_bleeding() {
+        let mut w = World::new(Light::new_point(Point::new(0, 10, 0), Color::white()));
+        w.add_object(Object::new(Plane));
+        // a red wall at x = 1
+        w.add_object(
+            Object::new(Plane)
+                .with_transform(
+                    Mat::identity()
+                        .rotate_z(std::f64::consts::PI / 2.0)
+                        .translate(1, 0, 0),
+                )
+                .with_material(Material::default().with_color(Color::new(1, 0, 0))),
+        );
+        let pt = PathTracer::new(&w).with_samples(200);
+        let r = Ray::new(Point::new(0.5, 1, 0), Vector::new(0, -1, 0));
+        let c = pt.color_at(&r, false);
+        assert!(c.red > c.green + 0.05);
+        assert_relative_eq!(c.green, c.blue, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn reproducible() {
+        let mut w = World::new(Light::new_point(Point::new(0, 10, 0), Color::white()));
+        w.add_object(Object::new(Plane));
+        w.add_object(Object::new(Sphere).with_transform(Mat::identity().translate(0, 1, 0)));
+        let r = Ray::new(Point::new(0, 1, -5), Vector::new(1.2, -1, 5).normalize());
+        let color = |seed| {
+            PathTracer::new(&w)
+                .with_seed(seed)
+                .with_samples(20)
+                .color_at(&r, false)
+        };
+        assert_eq!(color(1), color(1));
+        assert_ne!(color(1), color(2));
+    }
+
+    #[test]
+    fn render() {
+        let w = World::test_world();
+        let c = Camera::new(
+            11,
+            11,
+            std::f64::consts::PI / 2.0,
+            Point::new(0, 0, -5),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+            1,
+        );
+        let pt = PathTracer::new(&w).with_seed(7);
+        let image = c.render(&pt);
+        assert_eq!(image, c.render(&pt));
+        // the center of the image is the lit outer sphere
+        assert!(image.get(5, 5).green > 0.1);
+    }
+}
@@ -1,6 +1,55 @@
-use crate::{spaces, Color, Mat, Point};
+use crate::noise::{fbm, turbulence};
+use crate::{spaces, Color, CubeFace, Mat, Point, UvMapping, UvPattern, Vector};
+use std::f64::consts::PI;
+use std::fmt;
+use std::sync::Arc;
 
-/// Pattern defines a pattern of colors in object space
+/// The number of octaves of noise used by perturbed, marble and wood patterns
+const NOISE_OCTAVES: u32 = 4;
+
+/// How far turbulence displaces the veins of a marble pattern
+const MARBLE_TURBULENCE: f64 = 4.0;
+
+/// How far noise displaces the rings of a wood pattern
+const WOOD_NOISE: f64 = 0.3;
+
+/// A user-defined pattern, to be used with `Pattern::custom`.  Closures taking a point and
+/// returning a color implement this trait, as do boxed and shared trait objects.
+pub trait PatternFn: Send + Sync {
+    /// Calculate the color at the given point in pattern space.
+    fn color_at(&self, p: Point<spaces::Pattern>) -> Color;
+}
+
+impl<F> PatternFn for F
+where
+    F: Fn(Point<spaces::Pattern>) -> Color + Send + Sync,
+{
+    fn color_at(&self, p: Point<spaces::Pattern>) -> Color {
+        self(p)
+    }
+}
+
+impl PatternFn for Box<dyn PatternFn> {
+    fn color_at(&self, p: Point<spaces::Pattern>) -> Color {
+        (**self).color_at(p)
+    }
+}
+
+impl PatternFn for Arc<dyn PatternFn> {
+    fn color_at(&self, p: Point<spaces::Pattern>) -> Color {
+        (**self).color_at(p)
+    }
+}
+
+impl fmt::Debug for dyn PatternFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PatternFn")
+    }
+}
+
+/// Pattern defines a pattern of colors in object space.  Patterns nest, so the parts of a
+/// pattern may be any other pattern, or just a color; each nested pattern has its own
+/// transform from the same object space.
 #[derive(Debug, Clone)]
 pub struct Pattern {
     transform: Mat<4, spaces::Object, spaces::Pattern>,
@@ -10,15 +59,39 @@ pub struct Pattern {
 #[derive(Debug, Clone)]
 enum PatternImpl {
     Solid(Color),
-    Stripe(Color, Color),
-    Blend(Box<Pattern>, Box<Pattern>),
-    Gradient(Color, Color),
-    Checker(Color, Color),
-    Ring(Color, Color),
+    Stripe(Box<Pattern>, Box<Pattern>),
+    /// Two patterns and the weight of the second
+    Blend(Box<Pattern>, Box<Pattern>, f64),
+    /// A mask and the patterns to use where it is light and dark
+    Select(Box<Pattern>, Box<Pattern>, Box<Pattern>),
+    Gradient(Box<Pattern>, Box<Pattern>),
+    RadialGradient(Box<Pattern>, Box<Pattern>),
+    SphericalGradient(Box<Pattern>, Box<Pattern>),
+    Checker(Box<Pattern>, Box<Pattern>),
+    Ring(Box<Pattern>, Box<Pattern>),
+    TextureMap(UvPattern, UvMapping),
+    /// A pattern for each face, in the order of CubeFace
+    CubeMap(Box<[UvPattern; 6]>),
+    Perturbed(Box<Pattern>, f64),
+    Marble(Box<Pattern>, Box<Pattern>),
+    Wood(Box<Pattern>, Box<Pattern>),
+    Custom(Arc<dyn PatternFn>),
 }
 
 use PatternImpl::*;
 
+/// Interpolate between two patterns at the given point, using `a` where `t` is at most 0 and
+/// `b` where it is at least 1.
+fn mix(a: &Pattern, b: &Pattern, t: f64, obj_p: Point<spaces::Object>) -> Color {
+    if t <= 0.0 {
+        a.color_at(obj_p)
+    } else if t >= 1.0 {
+        b.color_at(obj_p)
+    } else {
+        a.color_at(obj_p) * (1.0 - t) + b.color_at(obj_p) * t
+    }
+}
+
 impl Pattern {
     fn new(pattern_impl: PatternImpl) -> Self {
         Self {
@@ -31,24 +104,89 @@ impl Pattern {
         Self::new(Solid(c))
     }
 
-    pub fn stripe(a: Color, b: Color) -> Self {
-        Self::new(Stripe(a, b))
+    pub fn stripe(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Self::new(Stripe(Box::new(a.into()), Box::new(b.into())))
+    }
+
+    /// Create a pattern averaging two patterns, weighting each equally.  (Earlier versions
+    /// returned `a + b / 2` rather than the average, so scenes using `blend` render darker.)
+    pub fn blend(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Self::blend_weighted(a, b, 0.5)
+    }
+
+    /// Create a pattern blending two patterns, where `weight` is the proportion of `b`, from 0
+    /// to 1.
+    pub fn blend_weighted(a: impl Into<Pattern>, b: impl Into<Pattern>, weight: f64) -> Self {
+        Self::new(Blend(Box::new(a.into()), Box::new(b.into()), weight))
+    }
+
+    /// Create a pattern using `a` where the mask pattern is light, and `b` where it is dark,
+    /// with the threshold at an average brightness of 0.5.
+    pub fn select(mask: impl Into<Pattern>, a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Self::new(Select(
+            Box::new(mask.into()),
+            Box::new(a.into()),
+            Box::new(b.into()),
+        ))
+    }
+
+    /// Create a pattern blending from `a` to `b` as x goes from 0 to 1.
+    pub fn gradient(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Self::new(Gradient(Box::new(a.into()), Box::new(b.into())))
+    }
+
+    /// Create a pattern blending from `a` on the y axis to `b` at a distance of 1 from it.
+    pub fn radial_gradient(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Self::new(RadialGradient(Box::new(a.into()), Box::new(b.into())))
     }
 
-    pub fn blend(a: Pattern, b: Pattern) -> Self {
-        Self::new(Blend(Box::new(a), Box::new(b)))
+    /// Create a pattern blending from `a` at the origin to `b` at a distance of 1 from it.
+    pub fn spherical_gradient(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Self::new(SphericalGradient(Box::new(a.into()), Box::new(b.into())))
     }
 
-    pub fn gradient(a: Color, b: Color) -> Self {
-        Self::new(Gradient(a, b - a))
+    pub fn checker(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Self::new(Checker(Box::new(a.into()), Box::new(b.into())))
     }
 
-    pub fn checker(a: Color, b: Color) -> Self {
-        Self::new(Checker(a, b))
+    pub fn ring(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Self::new(Ring(Box::new(a.into()), Box::new(b.into())))
     }
 
-    pub fn ring(a: Color, b: Color) -> Self {
-        Self::new(Ring(a, b))
+    /// Create a pattern wrapping a two-dimensional pattern around an object with the given
+    /// mapping.
+    pub fn texture_map(uv_pattern: UvPattern, mapping: UvMapping) -> Self {
+        Self::new(TextureMap(uv_pattern, mapping))
+    }
+
+    /// Create a pattern with a different two-dimensional pattern on each face of a cube: left,
+    /// front, right, back, up and down.
+    pub fn cube_map(faces: [UvPattern; 6]) -> Self {
+        Self::new(CubeMap(Box::new(faces)))
+    }
+
+    /// Create a pattern which jitters each point with noise, by up to about `scale` units in
+    /// object space, before finding its color in the given pattern.  The transform of this
+    /// pattern sets the frequency of the noise.
+    pub fn perturbed(pattern: impl Into<Pattern>, scale: f64) -> Self {
+        Self::new(Perturbed(Box::new(pattern.into()), scale))
+    }
+
+    /// Create a pattern of turbulent veins running across the x axis, blending from `a` to `b`
+    /// and back every two units.
+    pub fn marble(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Self::new(Marble(Box::new(a.into()), Box::new(b.into())))
+    }
+
+    /// Create a pattern of noisy rings around the y axis, each one unit wide and blending from
+    /// `a` to `b`.
+    pub fn wood(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Self::new(Wood(Box::new(a.into()), Box::new(b.into())))
+    }
+
+    /// Create a pattern from a user-defined function of points in pattern space.
+    pub fn custom(pattern_fn: impl PatternFn + 'static) -> Self {
+        Self::new(Custom(Arc::new(pattern_fn)))
     }
 
     /// Return an updated object with the given transform, where the transform is
@@ -63,41 +201,75 @@ impl Pattern {
 
     /// Calculate the color at the given point in object space.
     pub fn color_at(&self, obj_p: Point<spaces::Object>) -> Color {
+        if let Solid(c) = self.pattern_impl {
+            return c;
+        }
         let p = self.transform * obj_p;
         match self.pattern_impl {
             Solid(c) => c,
-            Stripe(a, b) => {
+            Stripe(ref a, ref b) => {
                 if p.x.rem_euclid(2.0) < 1.0 {
-                    a
+                    a.color_at(obj_p)
                 } else {
-                    b
+                    b.color_at(obj_p)
                 }
             }
-            Blend(ref a, ref b) => a.color_at(obj_p) + b.color_at(obj_p) / 2.0,
-            Gradient(a, b_minus_a) => {
-                if p.x < 0.0 {
-                    a
-                } else if p.x < 1.0 {
-                    a + (b_minus_a) * p.x.fract()
+            Blend(ref a, ref b, weight) => mix(a, b, weight, obj_p),
+            Select(ref mask, ref a, ref b) => {
+                let m = mask.color_at(obj_p);
+                if (m.red + m.green + m.blue) / 3.0 >= 0.5 {
+                    a.color_at(obj_p)
                 } else {
-                    a + (b_minus_a)
+                    b.color_at(obj_p)
                 }
             }
-            Checker(a, b) => {
+            Gradient(ref a, ref b) => mix(a, b, p.x, obj_p),
+            RadialGradient(ref a, ref b) => mix(a, b, (p.x * p.x + p.z * p.z).sqrt(), obj_p),
+            SphericalGradient(ref a, ref b) => {
+                mix(a, b, (p.x * p.x + p.y * p.y + p.z * p.z).sqrt(), obj_p)
+            }
+            Checker(ref a, ref b) => {
                 if (p.x.floor() + p.y.floor() + p.z.floor()).rem_euclid(2.0) < 1.0 {
-                    a
+                    a.color_at(obj_p)
                 } else {
-                    b
+                    b.color_at(obj_p)
                 }
             }
-            Ring(a, b) => {
+            Ring(ref a, ref b) => {
                 let d = (p.x * p.x + p.z * p.z).sqrt();
                 if d.rem_euclid(2.0) < 1.0 {
-                    a
+                    a.color_at(obj_p)
                 } else {
-                    b
+                    b.color_at(obj_p)
                 }
             }
+            TextureMap(ref uv_pattern, mapping) => {
+                let (u, v) = mapping.map(p);
+                uv_pattern.color_at(u, v)
+            }
+            CubeMap(ref faces) => {
+                let face = CubeFace::from_point(p);
+                let (u, v) = face.map(p);
+                faces[face as usize].color_at(u, v)
+            }
+            Perturbed(ref pattern, scale) => {
+                // sample the noise at distant points for each axis, so they are independent
+                let offset: Vector<spaces::Object> = Vector::new(
+                    fbm(p, NOISE_OCTAVES),
+                    fbm(p + Vector::new(31.4, 15.9, 26.5), NOISE_OCTAVES),
+                    fbm(p + Vector::new(-35.8, 97.9, -32.3), NOISE_OCTAVES),
+                );
+                pattern.color_at(obj_p + offset * scale)
+            }
+            Marble(ref a, ref b) => {
+                let x = p.x + MARBLE_TURBULENCE * turbulence(p, NOISE_OCTAVES);
+                mix(a, b, 0.5 - 0.5 * (x * PI).cos(), obj_p)
+            }
+            Wood(ref a, ref b) => {
+                let d = (p.x * p.x + p.z * p.z).sqrt() + WOOD_NOISE * fbm(p, NOISE_OCTAVES);
+                mix(a, b, d.rem_euclid(1.0), obj_p)
+            }
+            Custom(ref pattern_fn) => pattern_fn.color_at(p),
         }
     }
 }
@@ -195,4 +367,269 @@ mod test {
             Color::new(0.25, 0.25, 0.25)
         );
     }
+
+    #[test]
+    fn texture_map_spherical() {
+        let p = Pattern::texture_map(
+            UvPattern::Checkers {
+                width: 16.0,
+                height: 8.0,
+                a: Color::black(),
+                b: Color::white(),
+            },
+            UvMapping::Spherical,
+        );
+        for (point, expected) in [
+            (Point::new(0.4315, 0.4670, 0.7719), Color::white()),
+            (Point::new(-0.9654, 0.2552, -0.0534), Color::black()),
+            (Point::new(0.1039, 0.7090, 0.6975), Color::white()),
+            (Point::new(-0.4986, -0.7856, -0.3663), Color::black()),
+            (Point::new(-0.0317, -0.9395, 0.3411), Color::black()),
+            (Point::new(0.4809, -0.7721, 0.4154), Color::black()),
+            (Point::new(0.0285, -0.9612, -0.2745), Color::black()),
+            (Point::new(-0.5734, -0.2162, -0.7903), Color::white()),
+            (Point::new(0.7688, -0.1470, 0.6223), Color::black()),
+            (Point::new(-0.7652, 0.2175, 0.6060), Color::black()),
+        ] {
+            assert_eq!(p.color_at(point), expected);
+        }
+    }
+
+    #[test]
+    fn cube_map() {
+        let (red, yellow, brown) = (
+            Color::new(1, 0, 0),
+            Color::new(1, 1, 0),
+            Color::new(1, 0.5, 0),
+        );
+        let (green, cyan, blue) = (
+            Color::new(0, 1, 0),
+            Color::new(0, 1, 1),
+            Color::new(0, 0, 1),
+        );
+        let (purple, white) = (Color::new(1, 0, 1), Color::white());
+        let face =
+            |main, upper_left, upper_right, bottom_left, bottom_right| UvPattern::AlignCheck {
+                main,
+                upper_left,
+                upper_right,
+                bottom_left,
+                bottom_right,
+            };
+        let p = Pattern::cube_map([
+            face(yellow, cyan, red, blue, brown),
+            face(cyan, red, yellow, brown, green),
+            face(red, yellow, purple, green, white),
+            face(green, purple, cyan, white, blue),
+            face(brown, cyan, purple, red, yellow),
+            face(purple, brown, green, blue, white),
+        ]);
+        for (point, expected) in [
+            // left
+            (Point::new(-1, 0, 0), yellow),
+            (Point::new(-1, 0.9, -0.9), cyan),
+            (Point::new(-1, 0.9, 0.9), red),
+            (Point::new(-1, -0.9, -0.9), blue),
+            (Point::new(-1, -0.9, 0.9), brown),
+            // front
+            (Point::new(0, 0, 1), cyan),
+            (Point::new(-0.9, 0.9, 1), red),
+            (Point::new(0.9, -0.9, 1), green),
+            // right
+            (Point::new(1, 0, 0), red),
+            (Point::new(1, 0.9, 0.9), yellow),
+            // back
+            (Point::new(0, 0, -1), green),
+            (Point::new(0.9, 0.9, -1), purple),
+            // up
+            (Point::new(0, 1, 0), brown),
+            (Point::new(-0.9, 1, -0.9), cyan),
+            // down
+            (Point::new(0, -1, 0), purple),
+            (Point::new(-0.9, -1, 0.9), brown),
+        ] {
+            assert_eq!(p.color_at(point), expected);
+        }
+    }
+
+    #[test]
+    fn perturbed() {
+        let stripes = Pattern::stripe(Color::white(), Color::black());
+        let still = Pattern::perturbed(stripes.clone(), 0.0);
+        let jittered = Pattern::perturbed(stripes.clone(), 0.5);
+        let mut differences = 0;
+        for i in 0..100 {
+            let point = Point::new(i as f64 * 0.0731, 0.37, i as f64 * 0.0413);
+            assert_eq!(still.color_at(point), stripes.color_at(point));
+            if jittered.color_at(point) != stripes.color_at(point) {
+                differences += 1;
+            }
+        }
+        assert!(differences > 0 && differences < 50);
+
+        let solid = Pattern::perturbed(Color::new(0.2, 0.4, 0.6), 2.0);
+        assert_eq!(
+            solid.color_at(Point::new(0.3, 1.7, -4.2)),
+            Color::new(0.2, 0.4, 0.6)
+        );
+    }
+
+    #[test]
+    fn marble() {
+        let p = Pattern::marble(Color::white(), Color::black());
+        // turbulence vanishes at points with integer coordinates
+        assert_relative_eq!(p.color_at(Point::new(0, 0, 0)), Color::white());
+        assert_relative_eq!(p.color_at(Point::new(1, 5, 3)), Color::black());
+        assert_relative_eq!(p.color_at(Point::new(-2, 1, 7)), Color::white());
+        // elsewhere, veins are not simple stripes along x
+        assert!(p.color_at(Point::new(0.5, 0, 0)) != p.color_at(Point::new(0.5, 0.5, 0.5)));
+        for i in 0..100 {
+            let c = p.color_at(Point::new(i as f64 * 0.17, i as f64 * 0.05, 1.3));
+            assert!(c.red >= 0.0 && c.red <= 1.0);
+        }
+    }
+
+    #[test]
+    fn wood() {
+        let p = Pattern::wood(Color::black(), Color::white());
+        // noise vanishes at points with integer coordinates
+        assert_relative_eq!(p.color_at(Point::new(0, 0, 0)), Color::black());
+        assert_relative_eq!(p.color_at(Point::new(3, 2, 4)), Color::black());
+        for i in 0..100 {
+            let c = p.color_at(Point::new(i as f64 * 0.17, i as f64 * 0.05, 1.3));
+            assert!(c.red >= 0.0 && c.red < 1.0);
+        }
+    }
+
+    struct Dots {
+        radius: f64,
+    }
+
+    impl PatternFn for Dots {
+        fn color_at(&self, p: Point<spaces::Pattern>) -> Color {
+            let (x, z) = (p.x - p.x.round(), p.z - p.z.round());
+            if x * x + z * z < self.radius * self.radius {
+                Color::black()
+            } else {
+                Color::white()
+            }
+        }
+    }
+
+    #[test]
+    fn custom() {
+        let p = Pattern::custom(Dots { radius: 0.25 });
+        assert_eq!(p.color_at(Point::new(0, 0, 0)), Color::black());
+        assert_eq!(p.color_at(Point::new(0.5, 0, 0)), Color::white());
+        assert_eq!(p.color_at(Point::new(3.1, 7, -1.9)), Color::black());
+
+        let p = p.with_transform(Mat::identity().scale(2, 2, 2));
+        assert_eq!(p.color_at(Point::new(0.4, 0, 0)), Color::black());
+        assert_eq!(p.color_at(Point::new(1, 0, 0)), Color::white());
+    }
+
+    #[test]
+    fn custom_trait_object() {
+        let boxed: Box<dyn PatternFn> = Box::new(Dots { radius: 0.25 });
+        let p = Pattern::custom(boxed);
+        assert_eq!(p.color_at(Point::new(0, 0, 0)), Color::black());
+        assert_eq!(p.color_at(Point::new(0.5, 0, 0)), Color::white());
+
+        // a shared pattern function may be used by several patterns
+        let shared: Arc<dyn PatternFn> = Arc::new(Dots { radius: 0.25 });
+        let p1 = Pattern::custom(shared.clone());
+        let p2 = Pattern::custom(shared).with_transform(Mat::identity().translate(0.5, 0, 0));
+        assert_eq!(p1.color_at(Point::new(0, 0, 0)), Color::black());
+        assert_eq!(p2.color_at(Point::new(0, 0, 0)), Color::white());
+    }
+
+    #[test]
+    fn custom_closure() {
+        let p = Pattern::custom(|p: Point<spaces::Pattern>| Color::new(p.x, p.y, p.z))
+            .with_transform(Mat::identity().translate(1, 0, 0));
+        assert_eq!(p.color_at(Point::new(1, 2, 3)), Color::new(0, 2, 3));
+
+        // patterns can be nested in the built-in ones
+        let p = Pattern::perturbed(p, 0.0);
+        assert_eq!(p.color_at(Point::new(1, 2, 3)), Color::new(0, 2, 3));
+    }
+
+    #[test]
+    fn nested() {
+        let stripes = Pattern::stripe(Color::white(), Color::black());
+        let shifted = stripes
+            .clone()
+            .with_transform(Mat::identity().translate(1, 0, 0));
+        let p = Pattern::checker(stripes.clone(), shifted);
+        assert_relative_eq!(p.color_at(Point::new(0.5, 0, 0.5)), Color::white());
+        // the next square has the shifted stripes, each with its own transform
+        assert_relative_eq!(stripes.color_at(Point::new(1.5, 0, 0.5)), Color::black());
+        assert_relative_eq!(p.color_at(Point::new(1.5, 0, 0.5)), Color::white());
+        assert_relative_eq!(p.color_at(Point::new(1.5, 1, 0.5)), Color::black());
+    }
+
+    #[test]
+    fn gradient_of_patterns() {
+        let a = Pattern::stripe(Color::white(), Color::black());
+        let b = Pattern::solid(Color::new(1, 0, 0));
+        let p = Pattern::gradient(a, b);
+        assert_relative_eq!(p.color_at(Point::new(-1.5, 0, 0)), Color::white());
+        assert_relative_eq!(p.color_at(Point::new(0.5, 0, 0)), Color::new(1, 0.5, 0.5));
+        assert_relative_eq!(p.color_at(Point::new(2, 0, 0)), Color::new(1, 0, 0));
+    }
+
+    #[test]
+    fn blend() {
+        let (a, b) = (Color::new(1, 0, 0), Color::new(0, 0, 1));
+        let p = Pattern::blend(a, b);
+        assert_relative_eq!(p.color_at(Point::new(0, 0, 0)), Color::new(0.5, 0, 0.5));
+        let p = Pattern::blend_weighted(a, b, 0.25);
+        assert_relative_eq!(p.color_at(Point::new(0, 0, 0)), Color::new(0.75, 0, 0.25));
+    }
+
+    #[test]
+    fn blend_is_average() {
+        // not a + b / 2, which blend returned before it took a weight
+        let p = Pattern::blend(Color::new(0.2, 0.4, 1), Color::new(0.6, 0, 0.5));
+        assert_relative_eq!(p.color_at(Point::new(0, 0, 0)), Color::new(0.4, 0.2, 0.75));
+        let p = Pattern::blend(Color::white(), Color::black());
+        assert_relative_eq!(p.color_at(Point::new(3, -1, 2)), Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn select() {
+        let mask = Pattern::ring(Color::white(), Color::new(0.2, 0.2, 0.2));
+        let p = Pattern::select(
+            mask,
+            Pattern::stripe(Color::new(1, 0, 0), Color::new(0, 1, 0)),
+            Color::new(0, 0, 1),
+        );
+        assert_relative_eq!(p.color_at(Point::new(0.5, 0, 0)), Color::new(1, 0, 0));
+        assert_relative_eq!(p.color_at(Point::new(1.5, 0, 0)), Color::new(0, 0, 1));
+        assert_relative_eq!(p.color_at(Point::new(2.5, 0, 0)), Color::new(1, 0, 0));
+        assert_relative_eq!(p.color_at(Point::new(1.5, 0, 2)), Color::new(0, 1, 0));
+    }
+
+    #[test]
+    fn radial_gradient() {
+        let p = Pattern::radial_gradient(Color::white(), Color::black());
+        assert_relative_eq!(p.color_at(Point::new(0, 5, 0)), Color::white());
+        assert_relative_eq!(
+            p.color_at(Point::new(0.3, 1, 0.4)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_relative_eq!(p.color_at(Point::new(0, 0, -2)), Color::black());
+    }
+
+    #[test]
+    fn spherical_gradient() {
+        let p = Pattern::spherical_gradient(Color::white(), Color::black());
+        assert_relative_eq!(p.color_at(Point::new(0, 0, 0)), Color::white());
+        assert_relative_eq!(
+            p.color_at(Point::new(0, 0.25, 0)),
+            Color::new(0.75, 0.75, 0.75)
+        );
+        assert_relative_eq!(p.color_at(Point::new(0.6, 0, 0.8)), Color::black());
+        assert_relative_eq!(p.color_at(Point::new(0, 3, 0)), Color::black());
+    }
 }
@@ -0,0 +1,311 @@
+use crate::{Color, Framebuffer, ToneMap};
+use image::RgbImage;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Lines of plain PPM data are wrapped to this many columns.
+const PLAIN_WIDTH: usize = 70;
+
+/// The variants of the PPM format.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PpmFormat {
+    /// "P3", with decimal values as text, suitable for diffing
+    Plain,
+
+    /// "P6", with values as bytes
+    Binary,
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Write an 8-bit image in PPM format.
+///
+/// Plain output follows "The Ray Tracer Challenge": each row of the image starts a new line,
+/// and lines are wrapped to 70 columns.
+pub fn write_ppm(img: &RgbImage, mut w: impl Write, format: PpmFormat) -> io::Result<()> {
+    let magic = match format {
+        PpmFormat::Plain => "P3",
+        PpmFormat::Binary => "P6",
+    };
+    write!(w, "{}\n{} {}\n255\n", magic, img.width(), img.height())?;
+    match format {
+        PpmFormat::Plain => {
+            for row in img.rows() {
+                let values: Vec<String> = row.flat_map(|p| p.0).map(|v| v.to_string()).collect();
+                for line in textwrap::wrap(&values.join(" "), PLAIN_WIDTH) {
+                    writeln!(w, "{}", line)?;
+                }
+            }
+        }
+        PpmFormat::Binary => w.write_all(img.as_raw())?,
+    }
+    Ok(())
+}
+
+impl Framebuffer {
+    /// Read a PPM file.  See `read_ppm`.
+    pub fn load_ppm<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read_ppm(BufReader::new(File::open(path)?))
+    }
+
+    /// Write this framebuffer to a PPM file, using the given tone map.
+    pub fn save_ppm<P: AsRef<Path>>(
+        &self,
+        path: P,
+        tone_map: ToneMap,
+        format: PpmFormat,
+    ) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.write_ppm(&mut w, tone_map, format)?;
+        w.flush()
+    }
+
+    /// Write this framebuffer in PPM format, using the given tone map.
+    pub fn write_ppm(&self, w: impl Write, tone_map: ToneMap, format: PpmFormat) -> io::Result<()> {
+        write_ppm(&self.to_image(tone_map), w, format)
+    }
+
+    /// Read an image in plain (P3) or binary (P6) PPM format.  Values are scaled so that the
+    /// file's maximum value is 1.
+    pub fn read_ppm(mut r: impl Read) -> io::Result<Self> {
+        let mut data = vec![];
+        r.read_to_end(&mut data)?;
+        let mut tokens = Tokens {
+            data: &data,
+            pos: 0,
+        };
+
+        let format = match tokens.next() {
+            Some(b"P3") => PpmFormat::Plain,
+            Some(b"P6") => PpmFormat::Binary,
+            _ => return Err(invalid_data("not a PPM file")),
+        };
+        let width = tokens.number("width")?;
+        let height = tokens.number("height")?;
+        let maxval = tokens.number("maximum value")?;
+        if maxval == 0 || maxval > 65535 {
+            return Err(invalid_data(format!("invalid maximum value {}", maxval)));
+        }
+        let scale = 1.0 / maxval as f64;
+
+        // check the size against the remaining data before allocating, so that a corrupt header
+        // can't ask for more memory than the file could fill: binary values take one or two
+        // bytes, and plain values at least one digit and a separator
+        let count = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|n| n.checked_mul(3))
+            .ok_or_else(|| invalid_data("image too large"))?;
+        let remaining = data.len() - tokens.pos;
+        let min_size = match format {
+            PpmFormat::Plain => count.saturating_mul(2),
+            PpmFormat::Binary => count.saturating_mul(if maxval < 256 { 1 } else { 2 }) + 1,
+        };
+        if remaining < min_size {
+            return Err(invalid_data("not enough pixel data"));
+        }
+
+        let mut fb = Framebuffer::new(width, height);
+        let values: Vec<u32> = match format {
+            PpmFormat::Plain => (0..count)
+                .map(|_| tokens.number("value"))
+                .collect::<io::Result<_>>()?,
+            PpmFormat::Binary => {
+                // a single whitespace character separates the header from the data
+                let start = tokens.pos + 1;
+                let bytes = if maxval < 256 { 1 } else { 2 };
+                let raw = data
+                    .get(start..start + count * bytes)
+                    .ok_or_else(|| invalid_data("not enough pixel data"))?;
+                if bytes == 1 {
+                    raw.iter().map(|&v| v as u32).collect()
+                } else {
+                    raw.chunks(2)
+                        .map(|v| (v[0] as u32) << 8 | v[1] as u32)
+                        .collect()
+                }
+            }
+        };
+
+        for (p, v) in fb.pixels_mut().iter_mut().zip(values.chunks(3)) {
+            *p = Color::new(v[0] as f64, v[1] as f64, v[2] as f64) * scale;
+        }
+        Ok(fb)
+    }
+}
+
+/// Whitespace-separated tokens in a PPM file, skipping comments.
+struct Tokens<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn next(&mut self) -> Option<&'a [u8]> {
+        loop {
+            match self.data.get(self.pos)? {
+                b'#' => {
+                    while self.data.get(self.pos).is_some_and(|&c| c != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                c if c.is_ascii_whitespace() => self.pos += 1,
+                _ => break,
+            }
+        }
+        let start = self.pos;
+        while self
+            .data
+            .get(self.pos)
+            .is_some_and(|c| !c.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+        Some(&self.data[start..self.pos])
+    }
+
+    fn number(&mut self, what: &str) -> io::Result<u32> {
+        let token = self
+            .next()
+            .ok_or_else(|| invalid_data(format!("missing {}", what)))?;
+        std::str::from_utf8(token)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| {
+                invalid_data(format!(
+                    "invalid {} {:?}",
+                    what,
+                    String::from_utf8_lossy(token)
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::*;
+
+    fn plain(fb: &Framebuffer) -> String {
+        let mut buf = vec![];
+        fb.write_ppm(&mut buf, ToneMap::default(), PpmFormat::Plain)
+            .unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn header() {
+        let ppm = plain(&Framebuffer::new(5, 3));
+        let lines: Vec<_> = ppm.lines().take(3).collect();
+        assert_eq!(lines, vec!["P3", "5 3", "255"]);
+    }
+
+    #[test]
+    fn pixel_data() {
+        let mut fb = Framebuffer::new(5, 3);
+        fb.set(0, 0, Color::new(1.5, 0, 0));
+        fb.set(2, 1, Color::new(0, 0.5, 0));
+        fb.set(4, 2, Color::new(-0.5, 0, 1));
+        let ppm = plain(&fb);
+        let lines: Vec<_> = ppm.lines().skip(3).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "255 0 0 0 0 0 0 0 0 0 0 0 0 0 0",
+                "0 0 0 0 0 0 0 128 0 0 0 0 0 0 0",
+                "0 0 0 0 0 0 0 0 0 0 0 0 0 0 255",
+            ]
+        );
+    }
+
+    #[test]
+    fn long_lines() {
+        let mut fb = Framebuffer::new(10, 2);
+        fb.pixels_mut().fill(Color::new(1, 0.8, 0.6));
+        let ppm = plain(&fb);
+        let lines: Vec<_> = ppm.lines().skip(3).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204",
+                "153 255 204 153 255 204 153 255 204 153 255 204 153",
+                "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204",
+                "153 255 204 153 255 204 153 255 204 153 255 204 153",
+            ]
+        );
+    }
+
+    #[test]
+    fn ends_with_newline() {
+        assert!(plain(&Framebuffer::new(5, 3)).ends_with('\n'));
+    }
+
+    #[test]
+    fn binary() {
+        let mut fb = Framebuffer::new(2, 1);
+        fb.set(1, 0, Color::new(1, 0.5, 0));
+        let mut buf = vec![];
+        fb.write_ppm(&mut buf, ToneMap::default(), PpmFormat::Binary)
+            .unwrap();
+        assert_eq!(buf, b"P6\n2 1\n255\n\0\0\0\xff\x80\0");
+    }
+
+    #[test]
+    fn read_plain() {
+        let input = "P3\n# a comment\n2 2 # another\n15\n15 0 0  0 15 0\n0 0 15\n5 5 5\n";
+        let fb = Framebuffer::read_ppm(input.as_bytes()).unwrap();
+        assert_eq!((fb.width(), fb.height()), (2, 2));
+        assert_relative_eq!(fb.get(0, 0), Color::new(1, 0, 0));
+        assert_relative_eq!(fb.get(1, 0), Color::new(0, 1, 0));
+        assert_relative_eq!(fb.get(0, 1), Color::new(0, 0, 1));
+        assert_relative_eq!(fb.get(1, 1), Color::new(1, 1, 1) / 3.0);
+    }
+
+    #[test]
+    fn read_binary() {
+        let fb = Framebuffer::read_ppm(&b"P6 1 1 255\n\xff\x80\x00"[..]).unwrap();
+        assert_relative_eq!(fb.get(0, 0), Color::new(1, 128.0 / 255.0, 0));
+        let fb = Framebuffer::read_ppm(&b"P6 1 1 65535\n\xff\xff\x80\x00\x00\x00"[..]).unwrap();
+        assert_relative_eq!(fb.get(0, 0), Color::new(1, 32768.0 / 65535.0, 0));
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut fb = Framebuffer::new(30, 4);
+        for (i, p) in fb.pixels_mut().iter_mut().enumerate() {
+            let v = (i % 256) as f64 / 255.0;
+            *p = Color::new(v, 1.0 - v, 0.5);
+        }
+        for format in [PpmFormat::Plain, PpmFormat::Binary] {
+            let mut buf = vec![];
+            fb.write_ppm(&mut buf, ToneMap::default(), format).unwrap();
+            let read = Framebuffer::read_ppm(&buf[..]).unwrap();
+            for (c1, c2) in fb.pixels().iter().zip(read.pixels()) {
+                assert_relative_eq!(c1, c2, epsilon = 1.0 / 255.0);
+            }
+        }
+    }
+
+    #[test]
+    fn read_errors() {
+        for (input, message) in [
+            (&b"P5 1 1 255\n\0"[..], "not a PPM file"),
+            (b"P3 1", "missing height"),
+            (b"P3 1 x 255", "invalid height \"x\""),
+            (b"P3 1 1 0", "invalid maximum value 0"),
+            (b"P3 1 1 255 0 0    ", "missing value"),
+            (b"P3 1 1 255 0 0", "not enough pixel data"),
+            (b"P6 1 1 255\n\0\0", "not enough pixel data"),
+            // sizes are checked before allocating
+            (b"P3 65535 65535 255 0 0 0", "not enough pixel data"),
+            (b"P6 65535 65535 65535\n\0", "not enough pixel data"),
+            (b"P6 4294967295 4294967295 255\n\0", "image too large"),
+        ] {
+            let err = Framebuffer::read_ppm(input).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert_eq!(err.to_string(), message);
+        }
+    }
+}
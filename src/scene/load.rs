@@ -0,0 +1,1536 @@
+use crate::csg::{
+    load_obj, Cone, Csg, CsgOp, Cube, Cylinder, Group, Object, Plane, SmoothTriangle, Sphere,
+    Triangle,
+};
+use crate::scene::yaml::{self, Mark, Node, Value};
+use crate::{
+    Attenuation, Background, Camera, Color, ImageTexture, Light, Mat, Material, NormalModifier,
+    Pattern, Point, Space, TextureFilter, TextureWrap, UvMapping, UvPattern, Vector, World,
+};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use yaml_rust::ScanError;
+
+/// A world and a camera with which to view it, as described by a scene file.
+pub struct Scene {
+    pub world: World,
+    pub camera: Camera,
+}
+
+/// An error reading a scene file.
+#[derive(Debug)]
+pub enum SceneError {
+    /// The file could not be read
+    Io(std::io::Error),
+
+    /// The file is not valid YAML
+    Syntax(ScanError),
+
+    /// The file is valid YAML, but does not describe a scene.  Lines and columns are numbered
+    /// from 1.
+    Invalid {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(err) => write!(f, "{}", err),
+            SceneError::Syntax(err) => write!(f, "{}", err),
+            SceneError::Invalid {
+                line,
+                column,
+                message,
+            } => write!(f, "{} at line {} column {}", message, line, column),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SceneError::Io(err) => Some(err),
+            SceneError::Syntax(err) => Some(err),
+            SceneError::Invalid { .. } => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SceneError {
+    fn from(err: std::io::Error) -> Self {
+        SceneError::Io(err)
+    }
+}
+
+impl From<ScanError> for SceneError {
+    fn from(err: ScanError) -> Self {
+        SceneError::Syntax(err)
+    }
+}
+
+type Result<T> = std::result::Result<T, SceneError>;
+
+/// Read a scene file.  See `parse_scene`.  OBJ and image files named in the scene are found
+/// relative to the directory containing the scene file.
+pub fn load_scene<P: AsRef<Path>>(path: P) -> Result<Scene> {
+    let path = path.as_ref();
+    let input = std::fs::read_to_string(path)?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    Loader::new(base_dir).scene(&yaml::parse(&input)?)
+}
+
+/// Parse a scene in the YAML format used by "The Ray Tracer Challenge".  OBJ and image files
+/// named in the scene are found relative to the current directory.
+///
+/// A scene is a sequence of items, each of which is one of
+///
+/// * `add: camera`, with `width`, `height`, `field-of-view`, `from`, `to`, `up` and an
+///   optional `oversample` (default 1);
+/// * `add: light`, with `intensity` and either `at` for a point light, `at`, `direction`,
+///   `inner-angle` and `outer-angle` for a spot light, `direction` alone for a directional
+///   light, or `corner`, `uvec`, `usteps`, `vvec`, `vsteps` and optionally `jitter` for an
+///   area light, and optionally `attenuation` (`none`, `linear`, `inverse-square` or
+///   `[constant, linear, quadratic]`);
+/// * `add: background`, with `color` for a solid color, `horizon` and `zenith` for a sky
+///   gradient, or `image` for an equirectangular image;
+/// * `add: <shape>`, with optional `material` and `transform`, where the shape is one of
+///   `sphere`, `cube`, `plane`, `cylinder` or `cone` (with optional `min`, `max` and
+///   `closed`), `triangle` (with `p1` to `p3`), `smooth-triangle` (also with `n1` to `n3`),
+///   `group` (with `children`), `csg` (with `operation`, `left` and `right`) or `obj` (with
+///   `file`);
+/// * `define: <name>`, with `value` and optionally `extend`, naming a material, a transform,
+///   or an object to be used with `add: <name>`.
+///
+/// A material has any of `color`, `pattern`, `bump`, `ambient`, `diffuse`, `specular`,
+/// `shininess`, `reflective`, `transparency`, `refractive-index` and `emission` (a color).  A
+/// pattern has a `type` (`stripes`, `gradient`, `radial-gradient`, `spherical-gradient`,
+/// `rings`, `checkers`, `marble`, `wood`, `blended` with an optional `weight`, or `select` with
+/// a `mask`), either two `colors` or two nested `patterns` (each a pattern or a color), and an
+/// optional `transform`.  A pattern with `type: perturbed` instead has a nested `pattern`,
+/// jittered by noise, and a `scale` for the size of the jitter.  A pattern with `type: map`
+/// instead has a `mapping` (`spherical`, `planar`, `cylindrical` or `cube`) and a `uv_pattern`,
+/// or for a cube, one for each of `left`, `front`, `right`, `back`, `up` and `down`.  A UV
+/// pattern, or the name of a defined one, has a `type` of `checkers`, with `width`, `height`
+/// and `colors`, `align_check`, with `colors` named `main`, `ul`, `ur`, `bl` and `br`, or
+/// `image`, with `file` and optionally `filter` (`nearest` or `bilinear`) and `wrap` (`repeat`
+/// or `clamp`).  A bump has a `type` of `waves`, `ripples` or `noise`, with an `amplitude`,
+/// `emboss`, with an `amplitude` and a `pattern`, or `normal-map`, with a `mapping` and an
+/// image `file` as for a UV pattern (but read without sRGB conversion), and an optional
+/// `transform`.  A transform is a sequence of `[translate, x, y, z]`, `[scale, x, y, z]`,
+/// `[rotate-x, r]` (and y, z), `[shear, xy, xz, yx, yz, zx, zy]` or the names of defined
+/// transforms, applied in order.
+///
+/// The scene must contain exactly one camera and at least one light, and at most one
+/// background.
+pub fn parse_scene(input: &str) -> Result<Scene> {
+    Loader::new(Path::new("")).scene(&yaml::parse(input)?)
+}
+
+/// The shapes which can be added to a scene, other than defined objects.
+const SHAPES: &[&str] = &[
+    "sphere",
+    "cube",
+    "plane",
+    "cylinder",
+    "cone",
+    "triangle",
+    "smooth-triangle",
+    "group",
+    "csg",
+    "obj",
+];
+
+/// Merge the entries of two mappings, with those in `over` replacing those in `base`.
+fn merge(base: &[(Node, Node)], over: Vec<(Node, Node)>) -> Vec<(Node, Node)> {
+    let mut merged: Vec<(Node, Node)> = base
+        .iter()
+        .filter(|(k, _)| !over.iter().any(|(k2, _)| k.value == k2.value))
+        .cloned()
+        .collect();
+    merged.extend(over);
+    merged
+}
+
+fn invalid<T>(mark: Mark, message: impl Into<String>) -> Result<T> {
+    Err(SceneError::Invalid {
+        line: mark.line,
+        column: mark.column,
+        message: message.into(),
+    })
+}
+
+impl Node {
+    fn as_str(&self) -> Result<&str> {
+        match self.value {
+            Value::Scalar(ref s) => Ok(s),
+            _ => invalid(self.mark, "expected a string"),
+        }
+    }
+
+    fn as_f64(&self) -> Result<f64> {
+        match self.value {
+            Value::Scalar(ref s) => s
+                .parse()
+                .or_else(|_| invalid(self.mark, format!("invalid number {:?}", s))),
+            _ => invalid(self.mark, "expected a number"),
+        }
+    }
+
+    fn as_u32(&self) -> Result<u32> {
+        match self.value {
+            Value::Scalar(ref s) => s
+                .parse()
+                .or_else(|_| invalid(self.mark, format!("invalid integer {:?}", s))),
+            _ => invalid(self.mark, "expected an integer"),
+        }
+    }
+
+    fn as_positive_u32(&self) -> Result<u32> {
+        match self.as_u32()? {
+            0 => invalid(self.mark, "expected a positive integer"),
+            n => Ok(n),
+        }
+    }
+
+    fn as_bool(&self) -> Result<bool> {
+        match self.as_str()? {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            s => invalid(self.mark, format!("invalid boolean {:?}", s)),
+        }
+    }
+
+    fn as_seq(&self) -> Result<&[Node]> {
+        match self.value {
+            Value::Sequence(ref items) => Ok(items),
+            _ => invalid(self.mark, "expected a sequence"),
+        }
+    }
+
+    fn as_triple(&self) -> Result<[f64; 3]> {
+        match self.as_seq()? {
+            [x, y, z] => Ok([x.as_f64()?, y.as_f64()?, z.as_f64()?]),
+            _ => invalid(self.mark, "expected a sequence of 3 numbers"),
+        }
+    }
+
+    fn as_point<S: Space>(&self) -> Result<Point<S>> {
+        let [x, y, z] = self.as_triple()?;
+        Ok(Point::new(x, y, z))
+    }
+
+    fn as_vector<S: Space>(&self) -> Result<Vector<S>> {
+        let [x, y, z] = self.as_triple()?;
+        Ok(Vector::new(x, y, z))
+    }
+
+    fn as_color(&self) -> Result<Color> {
+        let [r, g, b] = self.as_triple()?;
+        Ok(Color::new(r, g, b))
+    }
+
+    fn as_fields(&self) -> Result<Fields<'_>> {
+        let Value::Mapping(ref entries) = self.value else {
+            return invalid(self.mark, "expected a mapping");
+        };
+        let mut fields = Fields {
+            mark: self.mark,
+            entries: vec![],
+        };
+        for (key, value) in entries {
+            let name = key.as_str()?;
+            if fields.entries.iter().any(|(n, _, _)| *n == name) {
+                return invalid(key.mark, format!("duplicate key {:?}", name));
+            }
+            fields.entries.push((name, key.mark, value));
+        }
+        Ok(fields)
+    }
+}
+
+/// The fields of a mapping, which are removed as they are used so that any remaining fields
+/// can be reported as unknown.
+struct Fields<'a> {
+    mark: Mark,
+    entries: Vec<(&'a str, Mark, &'a Node)>,
+}
+
+impl<'a> Fields<'a> {
+    /// Determine whether a field is present.
+    fn contains(&self, name: &str) -> bool {
+        self.entries.iter().any(|(n, _, _)| *n == name)
+    }
+
+    /// Take an optional field.
+    fn take(&mut self, name: &str) -> Option<&'a Node> {
+        let i = self.entries.iter().position(|(n, _, _)| *n == name)?;
+        Some(self.entries.remove(i).2)
+    }
+
+    /// Take a required field.
+    fn require(&mut self, name: &str) -> Result<&'a Node> {
+        match self.take(name) {
+            Some(node) => Ok(node),
+            None => invalid(self.mark, format!("missing key {:?}", name)),
+        }
+    }
+
+    /// Check that all fields have been used.
+    fn finish(self) -> Result<()> {
+        match self.entries.first() {
+            Some((name, mark, _)) => invalid(*mark, format!("unknown key {:?}", name)),
+            None => Ok(()),
+        }
+    }
+}
+
+struct Loader {
+    base_dir: PathBuf,
+
+    /// Values given by `define`, with any `extend` already applied
+    defines: HashMap<String, Node>,
+}
+
+impl Loader {
+    fn new(base_dir: &Path) -> Self {
+        Self {
+            base_dir: base_dir.to_path_buf(),
+            defines: HashMap::new(),
+        }
+    }
+
+    fn scene(mut self, root: &Node) -> Result<Scene> {
+        let mut camera = None;
+        let mut background = None;
+        let mut lights = vec![];
+        let mut objects = vec![];
+
+        for item in root.as_seq()? {
+            let mut fields = item.as_fields()?;
+            if let Some(name) = fields.take("define") {
+                self.define(name, fields)?;
+                continue;
+            }
+
+            let add = fields.require("add")?;
+            match add.as_str()? {
+                "camera" => {
+                    if camera.is_some() {
+                        return invalid(add.mark, "only one camera is supported");
+                    }
+                    camera = Some(self.camera(fields)?);
+                }
+                "background" => {
+                    if background.is_some() {
+                        return invalid(add.mark, "only one background is supported");
+                    }
+                    background = Some(self.background(fields)?);
+                }
+                "light" => lights.push(self.light(fields)?),
+                _ => objects.push(self.object(add, fields)?),
+            }
+        }
+
+        let Some(camera) = camera else {
+            return invalid(root.mark, "scene has no camera");
+        };
+        let mut lights = lights.into_iter();
+        let Some(light) = lights.next() else {
+            return invalid(root.mark, "scene has no light");
+        };
+        let mut world = World::new(light);
+        for light in lights {
+            world.add_light(light);
+        }
+        if let Some(background) = background {
+            world.set_background(background);
+        }
+        for obj in objects {
+            // a group may be empty, such as a mesh with no faces
+            world.add_group(obj);
+        }
+        Ok(Scene { world, camera })
+    }
+
+    fn define(&mut self, name: &Node, mut fields: Fields) -> Result<()> {
+        let value = fields.require("value")?;
+        let extend = fields.take("extend");
+        fields.finish()?;
+
+        let mut value = value.clone();
+        if let Some(base) = extend {
+            let base_value = self.lookup(base)?;
+            value.value = match (&base_value.value, value.value) {
+                (Value::Mapping(base), Value::Mapping(entries)) => {
+                    Value::Mapping(merge(base, entries))
+                }
+                (Value::Sequence(base), Value::Sequence(items)) => {
+                    Value::Sequence(base.iter().cloned().chain(items).collect())
+                }
+                _ => return invalid(base.mark, "cannot extend a value of a different kind"),
+            };
+        }
+
+        // an object based on another defined object takes that object's fields now, so that
+        // every defined object is based on a shape, and definitions cannot form a cycle
+        if let Value::Mapping(ref entries) = value.value {
+            let is_add = |k: &Node| matches!(k.value, Value::Scalar(ref s) if s == "add");
+            if let Some((_, add)) = entries.iter().find(|(k, _)| is_add(k)) {
+                if !SHAPES.contains(&add.as_str()?) {
+                    let Value::Mapping(ref template) = self.lookup(add)?.value else {
+                        return invalid(add.mark, "expected the name of an object");
+                    };
+                    let own = entries.iter().filter(|(k, _)| !is_add(k)).cloned();
+                    value.value = Value::Mapping(merge(template, own.collect()));
+                }
+            }
+        }
+
+        self.defines.insert(name.as_str()?.to_string(), value);
+        Ok(())
+    }
+
+    fn lookup(&self, name: &Node) -> Result<&Node> {
+        let name_str = name.as_str()?;
+        match self.defines.get(name_str) {
+            Some(node) => Ok(node),
+            None => invalid(name.mark, format!("undefined name {:?}", name_str)),
+        }
+    }
+
+    fn camera(&self, mut fields: Fields) -> Result<Camera> {
+        let width = fields.require("width")?.as_positive_u32()?;
+        let height = fields.require("height")?.as_positive_u32()?;
+        let fov = fields.require("field-of-view")?.as_f64()?;
+        let from = fields.require("from")?.as_point()?;
+        let to = fields.require("to")?.as_point()?;
+        let up = fields.require("up")?.as_vector()?;
+        let oversample = match fields.take("oversample") {
+            Some(node) => node.as_positive_u32()?,
+            None => 1,
+        };
+        fields.finish()?;
+        Ok(Camera::new(width, height, fov, from, to, up, oversample))
+    }
+
+    fn light(&self, mut fields: Fields) -> Result<Light> {
+        let intensity = fields.require("intensity")?.as_color()?;
+        let light = if fields.contains("corner") {
+            let corner = fields.require("corner")?.as_point()?;
+            let uvec = fields.require("uvec")?.as_vector()?;
+            let usteps = fields.require("usteps")?.as_positive_u32()?;
+            let vvec = fields.require("vvec")?.as_vector()?;
+            let vsteps = fields.require("vsteps")?.as_positive_u32()?;
+            let jitter = match fields.take("jitter") {
+                Some(node) => node.as_bool()?,
+                None => false,
+            };
+            Light::new_area(corner, uvec, usteps, vvec, vsteps, intensity).with_jitter(jitter)
+        } else if fields.contains("direction") && !fields.contains("at") {
+            Light::new_directional(fields.require("direction")?.as_vector()?, intensity)
+        } else if fields.contains("direction") {
+            Light::new_spot(
+                fields.require("at")?.as_point()?,
+                fields.require("direction")?.as_vector()?,
+                fields.require("inner-angle")?.as_f64()?,
+                fields.require("outer-angle")?.as_f64()?,
+                intensity,
+            )
+        } else {
+            Light::new_point(fields.require("at")?.as_point()?, intensity)
+        };
+        let light = match fields.take("attenuation") {
+            Some(node) => light.with_attenuation(Self::attenuation(node)?),
+            None => light,
+        };
+        fields.finish()?;
+        Ok(light)
+    }
+
+    fn background(&self, mut fields: Fields) -> Result<Background> {
+        let background = if let Some(file) = fields.take("image") {
+            let path = self.base_dir.join(file.as_str()?);
+            Background::load_image(&path)
+                .or_else(|err| invalid(file.mark, format!("reading {}: {}", path.display(), err)))?
+        } else if fields.contains("zenith") {
+            Background::Gradient {
+                horizon: fields.require("horizon")?.as_color()?,
+                zenith: fields.require("zenith")?.as_color()?,
+            }
+        } else {
+            Background::Solid(fields.require("color")?.as_color()?)
+        };
+        fields.finish()?;
+        Ok(background)
+    }
+
+    fn attenuation(node: &Node) -> Result<Attenuation> {
+        if let Value::Sequence(_) = node.value {
+            let [constant, linear, quadratic] = node.as_triple()?;
+            if constant <= 0.0 && linear <= 0.0 && quadratic <= 0.0 {
+                return invalid(node.mark, "attenuation needs a positive coefficient");
+            }
+            return Ok(Attenuation::Custom {
+                constant,
+                linear,
+                quadratic,
+            });
+        }
+        match node.as_str()? {
+            "none" => Ok(Attenuation::None),
+            "linear" => Ok(Attenuation::Linear),
+            "inverse-square" => Ok(Attenuation::InverseSquare),
+            other => invalid(node.mark, format!("unknown attenuation {:?}", other)),
+        }
+    }
+
+    /// Build an object from the fields of a mapping, with `add` already removed.
+    fn object(&self, add: &Node, mut fields: Fields) -> Result<Object> {
+        let obj = match add.as_str()? {
+            "sphere" => Object::new(Sphere),
+            "cube" => Object::new(Cube),
+            "plane" => Object::new(Plane),
+            "cylinder" => {
+                let (min, max, closed) = Self::extent(&mut fields)?;
+                Object::new(Cylinder::new(min, max, closed))
+            }
+            "cone" => {
+                let (min, max, closed) = Self::extent(&mut fields)?;
+                Object::new(Cone::new(min, max, closed))
+            }
+            "triangle" => Object::new(Triangle::new(
+                fields.require("p1")?.as_point()?,
+                fields.require("p2")?.as_point()?,
+                fields.require("p3")?.as_point()?,
+            )),
+            "smooth-triangle" => Object::new(SmoothTriangle::new(
+                fields.require("p1")?.as_point()?,
+                fields.require("p2")?.as_point()?,
+                fields.require("p3")?.as_point()?,
+                fields.require("n1")?.as_vector()?,
+                fields.require("n2")?.as_vector()?,
+                fields.require("n3")?.as_vector()?,
+            )),
+            "group" => {
+                let mut group = Group::new();
+                for child in fields.require("children")?.as_seq()? {
+                    group.add_child(self.child(child)?);
+                }
+                Object::group(group)
+            }
+            "csg" => {
+                let op_node = fields.require("operation")?;
+                let op = match op_node.as_str()? {
+                    "union" => CsgOp::Union,
+                    "intersection" => CsgOp::Intersection,
+                    "difference" => CsgOp::Difference,
+                    s => return invalid(op_node.mark, format!("unknown operation {:?}", s)),
+                };
+                let left = self.child(fields.require("left")?)?;
+                let right = self.child(fields.require("right")?)?;
+                Object::csg(Csg::new(op, left, right))
+            }
+            "obj" => {
+                let file = fields.require("file")?;
+                let path = self.base_dir.join(file.as_str()?);
+                load_obj(&path).or_else(|err| {
+                    invalid(file.mark, format!("reading {}: {}", path.display(), err))
+                })?
+            }
+            _ => {
+                // a defined object, whose fields are overridden by those given here
+                let template = self.lookup(add)?;
+                let mut template_fields = template.as_fields()?;
+                let template_add = template_fields.require("add")?;
+                for (name, mark, node) in fields.entries {
+                    template_fields.take(name);
+                    template_fields.entries.push((name, mark, node));
+                }
+                return self.object(template_add, template_fields);
+            }
+        };
+
+        let obj = match fields.take("material") {
+            Some(node) => obj.with_material(self.material(node)?),
+            None => obj,
+        };
+        let obj = match fields.take("transform") {
+            Some(node) => obj.with_transform(self.transform(node)?),
+            None => obj,
+        };
+        fields.finish()?;
+        Ok(obj)
+    }
+
+    /// Build an object from a child of a group or CSG.
+    fn child(&self, node: &Node) -> Result<Object> {
+        let mut fields = node.as_fields()?;
+        let add = fields.require("add")?;
+        self.object(add, fields)
+    }
+
+    /// Get the `min`, `max` and `closed` fields of a cylinder or cone.
+    fn extent(fields: &mut Fields) -> Result<(f64, f64, bool)> {
+        let min = match fields.take("min") {
+            Some(node) => node.as_f64()?,
+            None => f64::NEG_INFINITY,
+        };
+        let max = match fields.take("max") {
+            Some(node) => node.as_f64()?,
+            None => f64::INFINITY,
+        };
+        let closed = match fields.take("closed") {
+            Some(node) => node.as_bool()?,
+            None => false,
+        };
+        Ok((min, max, closed))
+    }
+
+    fn material(&self, node: &Node) -> Result<Material> {
+        let node = match node.value {
+            Value::Scalar(_) => self.lookup(node)?,
+            _ => node,
+        };
+        let mut fields = node.as_fields()?;
+        let mut material = Material::default();
+        if let Some(node) = fields.take("color") {
+            material = material.with_color(node.as_color()?);
+        }
+        if let Some(node) = fields.take("pattern") {
+            material = material.with_pattern(self.pattern(node)?);
+        }
+        if let Some(node) = fields.take("bump") {
+            material = material.with_normal_modifier(self.normal_modifier(node)?);
+        }
+        if let Some(node) = fields.take("ambient") {
+            material = material.with_ambient(node.as_f64()?);
+        }
+        if let Some(node) = fields.take("diffuse") {
+            material = material.with_diffuse(node.as_f64()?);
+        }
+        if let Some(node) = fields.take("specular") {
+            material = material.with_specular(node.as_f64()?);
+        }
+        if let Some(node) = fields.take("shininess") {
+            material = material.with_shininess(node.as_f64()?);
+        }
+        if let Some(node) = fields.take("reflective") {
+            material = material.with_reflectivity(node.as_f64()?);
+        }
+        let transparency = fields.take("transparency");
+        let refractive_index = fields.take("refractive-index");
+        if transparency.is_some() || refractive_index.is_some() {
+            let t = match transparency {
+                Some(node) if node.as_f64()? >= 1.0 => {
+                    return invalid(node.mark, "transparency must be less than 1");
+                }
+                Some(node) => node.as_f64()?,
+                None => material.transparency,
+            };
+            let ri = match refractive_index {
+                Some(node) => node.as_f64()?,
+                None => material.refractive_index,
+            };
+            material = material.with_transparency(t, ri);
+        }
+        if let Some(node) = fields.take("emission") {
+            material = material.with_emission(node.as_color()?);
+        }
+        fields.finish()?;
+        Ok(material)
+    }
+
+    fn pattern(&self, node: &Node) -> Result<Pattern> {
+        let mut fields = node.as_fields()?;
+        let type_node = fields.require("type")?;
+        let pattern = match type_node.as_str()? {
+            "blended" => {
+                let (a, b) = self.two_patterns(&mut fields)?;
+                match fields.take("weight") {
+                    Some(node) => Pattern::blend_weighted(a, b, node.as_f64()?),
+                    None => Pattern::blend(a, b),
+                }
+            }
+            "select" => {
+                let mask = self.sub_pattern(fields.require("mask")?)?;
+                let (a, b) = self.two_patterns(&mut fields)?;
+                Pattern::select(mask, a, b)
+            }
+            "map" => self.texture_map(&mut fields)?,
+            "perturbed" => {
+                let pattern = self.sub_pattern(fields.require("pattern")?)?;
+                let scale = fields.require("scale")?.as_f64()?;
+                Pattern::perturbed(pattern, scale)
+            }
+            t => {
+                let (a, b) = self.two_patterns(&mut fields)?;
+                match t {
+                    "stripes" => Pattern::stripe(a, b),
+                    "gradient" => Pattern::gradient(a, b),
+                    "radial-gradient" => Pattern::radial_gradient(a, b),
+                    "spherical-gradient" => Pattern::spherical_gradient(a, b),
+                    "rings" => Pattern::ring(a, b),
+                    "checkers" => Pattern::checker(a, b),
+                    "marble" => Pattern::marble(a, b),
+                    "wood" => Pattern::wood(a, b),
+                    _ => return invalid(type_node.mark, format!("unknown pattern {:?}", t)),
+                }
+            }
+        };
+        let pattern = match fields.take("transform") {
+            Some(node) => pattern.with_transform(self.transform(node)?),
+            None => pattern,
+        };
+        fields.finish()?;
+        Ok(pattern)
+    }
+
+    /// Parse a pattern nested in another, which may be just a color.
+    fn sub_pattern(&self, node: &Node) -> Result<Pattern> {
+        match node.value {
+            Value::Sequence(_) => Ok(node.as_color()?.into()),
+            _ => self.pattern(node),
+        }
+    }
+
+    /// Parse the two parts of a pattern: either `colors` or `patterns`.
+    fn two_patterns(&self, fields: &mut Fields) -> Result<(Pattern, Pattern)> {
+        if let Some(node) = fields.take("patterns") {
+            return match node.as_seq()? {
+                [a, b] => Ok((self.sub_pattern(a)?, self.sub_pattern(b)?)),
+                _ => invalid(node.mark, "expected 2 patterns"),
+            };
+        }
+        let (a, b) = Self::two_colors(fields.require("colors")?)?;
+        Ok((a.into(), b.into()))
+    }
+
+    fn texture_map(&self, fields: &mut Fields) -> Result<Pattern> {
+        let mapping = self.uv_mapping(fields.require("mapping")?)?;
+        if mapping == UvMapping::Cubic && !fields.contains("uv_pattern") {
+            let mut face = |name| self.uv_pattern(fields.require(name)?);
+            return Ok(Pattern::cube_map([
+                face("left")?,
+                face("front")?,
+                face("right")?,
+                face("back")?,
+                face("up")?,
+                face("down")?,
+            ]));
+        }
+        let uv_pattern = self.uv_pattern(fields.require("uv_pattern")?)?;
+        Ok(Pattern::texture_map(uv_pattern, mapping))
+    }
+
+    fn uv_mapping(&self, node: &Node) -> Result<UvMapping> {
+        Ok(match node.as_str()? {
+            "spherical" => UvMapping::Spherical,
+            "planar" => UvMapping::Planar,
+            "cylindrical" => UvMapping::Cylindrical,
+            "cube" => UvMapping::Cubic,
+            m => return invalid(node.mark, format!("unknown mapping {:?}", m)),
+        })
+    }
+
+    fn uv_pattern(&self, node: &Node) -> Result<UvPattern> {
+        let node = match node.value {
+            Value::Scalar(_) => self.lookup(node)?,
+            _ => node,
+        };
+        let mut fields = node.as_fields()?;
+        let type_node = fields.require("type")?;
+        let uv_pattern = match type_node.as_str()? {
+            "checkers" => {
+                let width = fields.require("width")?.as_f64()?;
+                let height = fields.require("height")?.as_f64()?;
+                let (a, b) = Self::two_colors(fields.require("colors")?)?;
+                UvPattern::Checkers {
+                    width,
+                    height,
+                    a,
+                    b,
+                }
+            }
+            "align_check" => {
+                let mut colors = fields.require("colors")?.as_fields()?;
+                let uv_pattern = UvPattern::AlignCheck {
+                    main: colors.require("main")?.as_color()?,
+                    upper_left: colors.require("ul")?.as_color()?,
+                    upper_right: colors.require("ur")?.as_color()?,
+                    bottom_left: colors.require("bl")?.as_color()?,
+                    bottom_right: colors.require("br")?.as_color()?,
+                };
+                colors.finish()?;
+                uv_pattern
+            }
+            "image" => UvPattern::Image(self.image_texture(&mut fields, true)?),
+            t => return invalid(type_node.mark, format!("unknown UV pattern {:?}", t)),
+        };
+        fields.finish()?;
+        Ok(uv_pattern)
+    }
+
+    /// Parse an image texture, with `file` and optionally `filter` and `wrap`.  The image
+    /// holds sRGB colors if `srgb` is true, or else linear data.
+    fn image_texture(&self, fields: &mut Fields, srgb: bool) -> Result<ImageTexture> {
+        let file = fields.require("file")?;
+        let path = self.base_dir.join(file.as_str()?);
+        let texture = if srgb {
+            ImageTexture::load(&path)
+        } else {
+            ImageTexture::load_linear(&path)
+        };
+        let mut texture = texture
+            .or_else(|err| invalid(file.mark, format!("reading {}: {}", path.display(), err)))?;
+        if let Some(node) = fields.take("filter") {
+            texture = texture.with_filter(match node.as_str()? {
+                "nearest" => TextureFilter::Nearest,
+                "bilinear" => TextureFilter::Bilinear,
+                f => return invalid(node.mark, format!("unknown filter {:?}", f)),
+            });
+        }
+        if let Some(node) = fields.take("wrap") {
+            texture = texture.with_wrap(match node.as_str()? {
+                "repeat" => TextureWrap::Repeat,
+                "clamp" => TextureWrap::Clamp,
+                w => return invalid(node.mark, format!("unknown wrap {:?}", w)),
+            });
+        }
+        Ok(texture)
+    }
+
+    fn normal_modifier(&self, node: &Node) -> Result<NormalModifier> {
+        let mut fields = node.as_fields()?;
+        let type_node = fields.require("type")?;
+        let modifier = match type_node.as_str()? {
+            "normal-map" => {
+                let mapping = self.uv_mapping(fields.require("mapping")?)?;
+                NormalModifier::normal_map(self.image_texture(&mut fields, false)?, mapping)
+            }
+            t => {
+                let amplitude = fields.require("amplitude")?.as_f64()?;
+                match t {
+                    "waves" => NormalModifier::waves(amplitude),
+                    "ripples" => NormalModifier::ripples(amplitude),
+                    "noise" => NormalModifier::noise(amplitude),
+                    "emboss" => {
+                        let pattern = self.sub_pattern(fields.require("pattern")?)?;
+                        NormalModifier::emboss(pattern, amplitude)
+                    }
+                    _ => return invalid(type_node.mark, format!("unknown bump {:?}", t)),
+                }
+            }
+        };
+        let modifier = match fields.take("transform") {
+            Some(node) => modifier.with_transform(self.transform(node)?),
+            None => modifier,
+        };
+        fields.finish()?;
+        Ok(modifier)
+    }
+
+    fn two_colors(node: &Node) -> Result<(Color, Color)> {
+        match node.as_seq()? {
+            [a, b] => Ok((a.as_color()?, b.as_color()?)),
+            _ => invalid(node.mark, "expected 2 colors"),
+        }
+    }
+
+    fn transform<S1: Space, S2: Space>(&self, node: &Node) -> Result<Mat<4, S1, S2>> {
+        let mut xform = Mat::identity();
+        for step in node.as_seq()? {
+            if let Value::Scalar(_) = step.value {
+                xform = self.transform(self.lookup(step)?)? * xform;
+                continue;
+            }
+
+            let (op, args) = match step.as_seq()? {
+                [op, args @ ..] => (op, args),
+                [] => return invalid(step.mark, "empty transform"),
+            };
+            let args = args
+                .iter()
+                .map(Node::as_f64)
+                .collect::<Result<Vec<f64>>>()?;
+            let op_str = op.as_str()?;
+            let expected = match op_str {
+                "translate" | "scale" => 3,
+                "rotate-x" | "rotate-y" | "rotate-z" => 1,
+                "shear" => 6,
+                _ => return invalid(op.mark, format!("unknown transform {:?}", op_str)),
+            };
+            if args.len() != expected {
+                return invalid(
+                    step.mark,
+                    format!("expected {} values for {}", expected, op_str),
+                );
+            }
+            xform = match op_str {
+                "translate" => xform.translate(args[0], args[1], args[2]),
+                "scale" => xform.scale(args[0], args[1], args[2]),
+                "rotate-x" => xform.rotate_x(args[0]),
+                "rotate-y" => xform.rotate_y(args[0]),
+                "rotate-z" => xform.rotate_z(args[0]),
+                _ => xform.shear(args[0], args[1], args[2], args[3], args[4], args[5]),
+            };
+        }
+        Ok(xform)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::*;
+    use approx::*;
+
+    const CAMERA_AND_LIGHT: &str = "
+- add: camera
+  width: 100
+  height: 50
+  field-of-view: 0.785
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+
+- add: light
+  at: [-10, 10, -10]
+  intensity: [1, 1, 1]
+";
+
+    fn scene(items: &str) -> Scene {
+        match parse_scene(&format!("{}{}", CAMERA_AND_LIGHT, items)) {
+            Ok(scene) => scene,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Parse an invalid scene, returning the error's message, line and column.
+    fn error(input: &str) -> (String, usize, usize) {
+        match parse_scene(input) {
+            Err(SceneError::Invalid {
+                line,
+                column,
+                message,
+            }) => (message, line, column),
+            Err(err) => panic!("unexpected error {}", err),
+            Ok(_) => panic!("expected an error"),
+        }
+    }
+
+    #[test]
+    fn camera_and_light() {
+        let s = scene("");
+        assert_eq!(s.camera.hsize(), 100);
+        assert_eq!(s.camera.vsize(), 50);
+        assert_eq!(s.world.lights.len(), 1);
+        assert_relative_eq!(s.world.lights[0].position, Point::new(-10, 10, -10));
+        assert_relative_eq!(s.world.lights[0].intensity, Color::white());
+    }
+
+    #[test]
+    fn multiple_lights() {
+        let s = scene(
+            "
+- add: light
+  at: [10, 10, -10]
+  intensity: [0.5, 0.5, 0.5]
+",
+        );
+        assert_eq!(s.world.lights.len(), 2);
+        assert_relative_eq!(s.world.lights[1].position, Point::new(10, 10, -10));
+    }
+
+    #[test]
+    fn area_light() {
+        let s = scene(
+            "
+- add: light
+  corner: [-1, 2, 4]
+  uvec: [2, 0, 0]
+  vvec: [0, 2, 0]
+  usteps: 10
+  vsteps: 5
+  jitter: true
+  intensity: [1.5, 1.5, 1.5]
+",
+        );
+        let light = s.world.lights[1];
+        assert_relative_eq!(light.position, Point::new(0, 3, 4));
+        assert_eq!(light.samples(), 50);
+    }
+
+    #[test]
+    fn area_light_without_steps() {
+        let input = format!(
+            "{}- add: light\n  corner: [0, 2, 0]\n  uvec: [1, 0, 0]\n  usteps: 0\n  \
+             vvec: [0, 1, 0]\n  vsteps: 2\n  intensity: [1, 1, 1]\n",
+            CAMERA_AND_LIGHT
+        );
+        assert_eq!(
+            error(&input),
+            ("expected a positive integer".to_string(), 16, 11)
+        );
+    }
+
+    #[test]
+    fn light_attenuation() {
+        let s = scene(
+            "
+- add: light
+  at: [0, 10, 0]
+  intensity: [1, 1, 1]
+  attenuation: inverse-square
+- add: light
+  at: [0, 10, 0]
+  intensity: [1, 1, 1]
+  attenuation: [1, 0, 0.5]
+",
+        );
+        assert_eq!(s.world.lights[0].attenuation, Attenuation::None);
+        assert_eq!(s.world.lights[1].attenuation, Attenuation::InverseSquare);
+        assert_eq!(
+            s.world.lights[2].attenuation,
+            Attenuation::Custom {
+                constant: 1.0,
+                linear: 0.0,
+                quadratic: 0.5
+            }
+        );
+    }
+
+    #[test]
+    fn zero_attenuation() {
+        let input = format!(
+            "{}- add: light\n  at: [0, 10, 0]\n  intensity: [1, 1, 1]\n  attenuation: [0, 0, 0]\n",
+            CAMERA_AND_LIGHT
+        );
+        assert_eq!(
+            error(&input),
+            (
+                "attenuation needs a positive coefficient".to_string(),
+                16,
+                16
+            )
+        );
+    }
+
+    #[test]
+    fn spot_and_directional_lights() {
+        let s = scene(
+            "
+- add: light
+  at: [0, 10, 0]
+  direction: [0, -1, 0]
+  inner-angle: 0.5
+  outer-angle: 0.7
+  intensity: [1, 1, 1]
+- add: light
+  direction: [1, -1, 0]
+  intensity: [0.5, 0.5, 0.5]
+",
+        );
+        assert_eq!(
+            s.world.lights[1],
+            Light::new_spot(
+                Point::new(0, 10, 0),
+                Vector::new(0, -1, 0),
+                0.5,
+                0.7,
+                Color::white()
+            )
+        );
+        assert_eq!(
+            s.world.lights[2],
+            Light::new_directional(Vector::new(1, -1, 0), Color::new(0.5, 0.5, 0.5))
+        );
+    }
+
+    #[test]
+    fn primitives() {
+        let s = scene(
+            "
+- add: sphere
+- add: cube
+- add: plane
+- add: cylinder
+  min: 0
+  max: 1
+  closed: true
+- add: cone
+- add: triangle
+  p1: [0, 1, 0]
+  p2: [-1, 0, 0]
+  p3: [1, 0, 0]
+- add: smooth-triangle
+  p1: [0, 1, 0]
+  p2: [-1, 0, 0]
+  p3: [1, 0, 0]
+  n1: [0, 1, 0]
+  n2: [-1, 0, 0]
+  n3: [1, 0, 0]
+",
+        );
+        assert_eq!(s.world.objects.len(), 7);
+    }
+
+    #[test]
+    fn groups_and_csg() {
+        let s = scene(
+            "
+- add: group
+  transform:
+    - [translate, 0, 0, 1]
+  children:
+    - add: sphere
+    - add: csg
+      operation: difference
+      left:
+        add: cube
+      right:
+        add: sphere
+        transform:
+          - [scale, 0.5, 0.5, 0.5]
+",
+        );
+        assert_eq!(s.world.objects.len(), 3);
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+        let mut inters = Intersections::default();
+        s.world.intersect(&r, &mut inters);
+        assert_relative_eq!(inters.iter().next().unwrap().t, 5.0);
+    }
+
+    #[test]
+    fn define_and_extend_material() {
+        let s = scene(
+            "
+- define: white
+  value:
+    color: [1, 1, 1]
+    diffuse: 0.7
+    ambient: 0.2
+- define: blue
+  extend: white
+  value:
+    color: [0, 0, 1]
+- add: sphere
+  material: blue
+",
+        );
+        let m = s.world.objects[0].material();
+        assert_relative_eq!(m.pattern.color_at(Point::new(0, 0, 0)), Color::new(0, 0, 1));
+        assert_relative_eq!(m.diffuse, 0.7);
+        assert_relative_eq!(m.ambient, 0.2);
+        assert_relative_eq!(m.specular, 0.9);
+    }
+
+    #[test]
+    fn material_properties() {
+        let s = scene(
+            "
+- add: sphere
+  material:
+    pattern:
+      type: stripes
+      colors: [[1, 0, 0], [0, 1, 0]]
+      transform:
+        - [scale, 0.5, 0.5, 0.5]
+    specular: 0.3
+    shininess: 50
+    reflective: 0.5
+    transparency: 0.9
+    refractive-index: 1.5
+    emission: [2, 1, 0]
+",
+        );
+        let m = s.world.objects[0].material();
+        assert_relative_eq!(
+            m.pattern.color_at(Point::new(0.25, 0, 0)),
+            Color::new(1, 0, 0)
+        );
+        assert_relative_eq!(
+            m.pattern.color_at(Point::new(0.75, 0, 0)),
+            Color::new(0, 1, 0)
+        );
+        assert_relative_eq!(m.specular, 0.3);
+        assert_relative_eq!(m.shininess, 50.0);
+        assert_relative_eq!(m.reflectivity, 0.5);
+        assert_relative_eq!(m.transparency, 0.9);
+        assert_relative_eq!(m.refractive_index, 1.5);
+        assert_relative_eq!(m.emission, Color::new(2, 1, 0));
+    }
+
+    #[test]
+    fn texture_maps() {
+        let s = scene(
+            "
+- add: sphere
+  material:
+    pattern:
+      type: map
+      mapping: spherical
+      uv_pattern:
+        type: checkers
+        width: 16
+        height: 8
+        colors: [[0, 0, 0], [1, 1, 1]]
+- define: face
+  value:
+    type: align_check
+    colors:
+      main: [1, 1, 1]
+      ul: [1, 0, 0]
+      ur: [1, 1, 0]
+      bl: [0, 1, 0]
+      br: [0, 1, 1]
+- add: cube
+  material:
+    pattern:
+      type: map
+      mapping: cube
+      left: face
+      front: face
+      right: face
+      back: face
+      up: face
+      down:
+        type: checkers
+        width: 2
+        height: 2
+        colors: [[0, 0, 0], [1, 1, 1]]
+",
+        );
+        let sphere = s.world.objects[0].material();
+        assert_relative_eq!(
+            sphere.pattern.color_at(Point::new(0.4315, 0.4670, 0.7719)),
+            Color::white()
+        );
+        let cube = s.world.objects[1].material();
+        assert_relative_eq!(
+            cube.pattern.color_at(Point::new(-1, 0.9, -0.9)),
+            Color::new(1, 0, 0)
+        );
+        assert_relative_eq!(
+            cube.pattern.color_at(Point::new(-0.5, -1, -0.5)),
+            Color::black()
+        );
+    }
+
+    #[test]
+    fn nested_patterns() {
+        let s = scene(
+            "
+- add: sphere
+  material:
+    pattern:
+      type: checkers
+      patterns:
+        - type: stripes
+          colors: [[1, 1, 1], [0, 0, 0]]
+        - [1, 0, 0]
+- add: sphere
+  material:
+    pattern:
+      type: select
+      mask:
+        type: radial-gradient
+        colors: [[1, 1, 1], [0, 0, 0]]
+      patterns:
+        - type: blended
+          weight: 0.25
+          colors: [[1, 0, 0], [0, 0, 1]]
+        - type: spherical-gradient
+          colors: [[0, 0, 0], [0, 1, 0]]
+",
+        );
+        let checkers = &s.world.objects[0].material().pattern;
+        assert_relative_eq!(checkers.color_at(Point::new(0.5, 0, 0)), Color::white());
+        assert_relative_eq!(
+            checkers.color_at(Point::new(1.5, 0, 0)),
+            Color::new(1, 0, 0)
+        );
+        assert_relative_eq!(checkers.color_at(Point::new(1.5, 1, 0)), Color::black());
+        let select = &s.world.objects[1].material().pattern;
+        assert_relative_eq!(
+            select.color_at(Point::new(0.1, 0, 0)),
+            Color::new(0.75, 0, 0.25)
+        );
+        assert_relative_eq!(select.color_at(Point::new(0, 0, 2)), Color::new(0, 1, 0));
+    }
+
+    #[test]
+    fn bumps() {
+        let s = scene(
+            "
+- add: plane
+  material:
+    bump:
+      type: waves
+      amplitude: 0.1
+      transform:
+        - [scale, 2, 2, 2]
+- add: plane
+  material:
+    bump:
+      type: emboss
+      amplitude: 0.5
+      pattern:
+        type: gradient
+        colors: [[0, 0, 0], [1, 1, 1]]
+",
+        );
+        let up = Vector::new(0, 1, 0);
+        let waves = s.world.objects[0].material();
+        assert_relative_eq!(
+            waves.normal_at(Point::new(0, 0, 0), up),
+            Vector::new(-0.1 * std::f64::consts::PI, 1, 0).normalize(),
+            epsilon = 1e-6
+        );
+        let emboss = s.world.objects[1].material();
+        assert_relative_eq!(
+            emboss.normal_at(Point::new(0.5, 0, 0), up),
+            Vector::new(-0.5, 1, 0).normalize(),
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn normal_map() {
+        let dir = std::env::temp_dir().join(format!("rtc-normal-map-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("normals.ppm"), "P3 1 1 2\n2 1 2\n").unwrap();
+        let path = dir.join("scene.yml");
+        std::fs::write(
+            &path,
+            "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+- add: light
+  at: [0, 10, 0]
+  intensity: [1, 1, 1]
+- add: plane
+  material:
+    bump:
+      type: normal-map
+      mapping: planar
+      file: normals.ppm
+      filter: bilinear
+",
+        )
+        .unwrap();
+        let s = load_scene(&path);
+        std::fs::remove_dir_all(&dir).unwrap();
+        let s = s.unwrap();
+        let plane = s.world.objects[0].material();
+        assert_relative_eq!(
+            plane.normal_at(Point::new(0.3, 0, 0.6), Vector::new(0, 1, 0)),
+            Vector::new(1, 1, 0).normalize(),
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn noise_patterns() {
+        let s = scene(
+            "
+- add: sphere
+  material:
+    pattern:
+      type: marble
+      colors: [[1, 1, 1], [0, 0, 0]]
+- add: plane
+  material:
+    pattern:
+      type: perturbed
+      scale: 0.5
+      pattern:
+        type: wood
+        colors: [[0, 0, 0], [1, 1, 1]]
+      transform:
+        - [scale, 2, 2, 2]
+",
+        );
+        let sphere = s.world.objects[0].material();
+        assert_relative_eq!(sphere.pattern.color_at(Point::new(1, 0, 0)), Color::black());
+        let plane = s.world.objects[1].material();
+        let expected = Pattern::perturbed(Pattern::wood(Color::black(), Color::white()), 0.5)
+            .with_transform(Mat::identity().scale(2, 2, 2));
+        for p in [Point::new(0, 0, 0), Point::new(0.3, 0, 2.9)] {
+            assert_relative_eq!(plane.pattern.color_at(p), expected.color_at(p));
+        }
+    }
+
+    #[test]
+    fn image_texture() {
+        // files are found relative to the scene file
+        let dir = std::env::temp_dir().join(format!("rtc-image-texture-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("texture.ppm"), "P3 2 1 1\n1 0 0 0 0 1\n").unwrap();
+        let input = format!(
+            "{}- add: plane
+  material:
+    pattern:
+      type: map
+      mapping: planar
+      uv_pattern:
+        type: image
+        file: texture.ppm
+        filter: bilinear
+        wrap: clamp
+",
+            CAMERA_AND_LIGHT
+        );
+        std::fs::write(dir.join("scene.yaml"), input).unwrap();
+        let s = load_scene(dir.join("scene.yaml"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let s = s.unwrap();
+        let m = s.world.objects[0].material();
+        assert_relative_eq!(
+            m.pattern.color_at(Point::new(0.1, 0, 0.5)),
+            Color::new(1, 0, 0)
+        );
+        assert_relative_eq!(
+            m.pattern.color_at(Point::new(0.5, 0, 0.5)),
+            Color::new(0.5, 0, 0.5)
+        );
+    }
+
+    #[test]
+    fn define_transforms_and_objects() {
+        let s = scene(
+            "
+- define: shift
+  value:
+    - [translate, 0, 0, 1]
+- define: big-shifted
+  value:
+    - [scale, 2, 2, 2]
+    - shift
+- define: ball
+  value:
+    add: sphere
+    transform:
+      - big-shifted
+- add: ball
+",
+        );
+        // the sphere is scaled by 2 and then moved to z = 1, so spans -1..3
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+        let mut inters = Intersections::default();
+        s.world.intersect(&r, &mut inters);
+        let ts: Vec<f64> = inters.iter().map(|i| i.t).collect();
+        assert_relative_eq!(ts[..], [4.0, 8.0][..]);
+    }
+
+    #[test]
+    fn defined_objects_cannot_cycle() {
+        let input = format!(
+            "{}- define: ball\n  value:\n    add: sphere\n\
+             - define: ball\n  value:\n    add: ball\n\
+             - define: orb\n  value:\n    add: orb\n",
+            CAMERA_AND_LIGHT
+        );
+        // the second definition of ball is based on the first
+        assert_eq!(
+            error(&input),
+            ("undefined name \"orb\"".to_string(), 21, 10)
+        );
+    }
+
+    #[test]
+    fn background() {
+        let s = scene("- add: background\n  horizon: [1, 1, 1]\n  zenith: [0, 0, 1]\n");
+        assert_eq!(
+            s.world.background,
+            Background::Gradient {
+                horizon: Color::white(),
+                zenith: Color::new(0, 0, 1)
+            }
+        );
+        let s = scene("- add: background\n  color: [0.5, 0.5, 0.5]\n");
+        assert_eq!(
+            s.world.background,
+            Background::Solid(Color::new(0.5, 0.5, 0.5))
+        );
+    }
+
+    #[test]
+    fn missing_background_image() {
+        let input = format!(
+            "{}- add: background\n  image: no-such-file.png\n",
+            CAMERA_AND_LIGHT
+        );
+        let (message, line, column) = error(&input);
+        assert!(message.starts_with("reading no-such-file.png: "));
+        assert_eq!((line, column), (14, 10));
+    }
+
+    #[test]
+    fn missing_camera() {
+        assert_eq!(
+            error("- add: sphere\n"),
+            ("scene has no camera".to_string(), 1, 1)
+        );
+    }
+
+    #[test]
+    fn unknown_key() {
+        let input = format!("{}- add: sphere\n  colour: [1, 0, 0]\n", CAMERA_AND_LIGHT);
+        assert_eq!(error(&input), ("unknown key \"colour\"".to_string(), 14, 3));
+    }
+
+    #[test]
+    fn invalid_value() {
+        let input = format!(
+            "{}- add: sphere\n  material:\n    diffuse: lots\n",
+            CAMERA_AND_LIGHT
+        );
+        assert_eq!(
+            error(&input),
+            ("invalid number \"lots\"".to_string(), 15, 14)
+        );
+    }
+
+    #[test]
+    fn undefined_name() {
+        let input = format!("{}- add: sphere\n  material: shiny\n", CAMERA_AND_LIGHT);
+        assert_eq!(
+            error(&input),
+            ("undefined name \"shiny\"".to_string(), 14, 13)
+        );
+    }
+
+    #[test]
+    fn syntax_error() {
+        assert!(matches!(
+            parse_scene("- add: [camera\n"),
+            Err(SceneError::Syntax(_))
+        ));
+    }
+
+    #[test]
+    fn example_scenes() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/scenes");
+        let mut count = 0;
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|e| e == "yaml") {
+                if let Err(err) = load_scene(&path) {
+                    panic!("{}: {}", path.display(), err);
+                }
+                count += 1;
+            }
+        }
+        assert!(count > 0);
+    }
+}
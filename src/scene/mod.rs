@@ -0,0 +1,4 @@
+mod load;
+mod yaml;
+
+pub use load::*;
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::{Marker, ScanError};
+
+/// A YAML node, annotated with its position in the input.
+///
+/// Unlike `yaml_rust::Yaml`, this keeps the position of every node, so that errors can point
+/// to the offending value.  Scalars are kept as strings and interpreted on use.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Node {
+    pub(crate) value: Value,
+    pub(crate) mark: Mark,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Scalar(String),
+    Sequence(Vec<Node>),
+    /// A mapping, with keys in the order they appear
+    Mapping(Vec<(Node, Node)>),
+}
+
+/// A position in the input, with lines and columns numbered from 1.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Mark {
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+}
+
+impl From<Marker> for Mark {
+    fn from(m: Marker) -> Self {
+        Self {
+            line: m.line(),
+            column: m.col() + 1,
+        }
+    }
+}
+
+/// Parse a single YAML document into a tree of nodes.  An empty document is an empty sequence.
+pub(crate) fn parse(input: &str) -> Result<Node, ScanError> {
+    let mut builder = Builder::default();
+    Parser::new(input.chars()).load(&mut builder, false)?;
+    Ok(builder.root.unwrap_or(Node {
+        value: Value::Sequence(vec![]),
+        mark: Mark { line: 1, column: 1 },
+    }))
+}
+
+/// Build a tree of nodes from parser events.
+#[derive(Default)]
+struct Builder {
+    /// Partially-built sequences and mappings
+    stack: Vec<Frame>,
+
+    /// Anchored nodes, for resolving aliases
+    anchors: HashMap<usize, Node>,
+
+    root: Option<Node>,
+}
+
+/// A partially-built sequence or mapping.
+struct Frame {
+    node: Node,
+    anchor: usize,
+
+    /// For a mapping, a key still waiting for its value
+    key: Option<Node>,
+}
+
+impl Builder {
+    fn start(&mut self, value: Value, mark: Mark, anchor: usize) {
+        self.stack.push(Frame {
+            node: Node { value, mark },
+            anchor,
+            key: None,
+        });
+    }
+
+    fn push(&mut self, node: Node, anchor: usize) {
+        if anchor > 0 {
+            self.anchors.insert(anchor, node.clone());
+        }
+        let Some(frame) = self.stack.last_mut() else {
+            self.root = Some(node);
+            return;
+        };
+        match frame.node.value {
+            Value::Sequence(ref mut items) => items.push(node),
+            Value::Mapping(ref mut entries) => match frame.key.take() {
+                Some(key) => entries.push((key, node)),
+                None => frame.key = Some(node),
+            },
+            Value::Scalar(_) => unreachable!(),
+        }
+    }
+}
+
+impl MarkedEventReceiver for Builder {
+    fn on_event(&mut self, ev: Event, mark: Marker) {
+        let mark = mark.into();
+        match ev {
+            Event::Scalar(s, _, anchor, _) => self.push(
+                Node {
+                    value: Value::Scalar(s),
+                    mark,
+                },
+                anchor,
+            ),
+            Event::SequenceStart(anchor) => self.start(Value::Sequence(vec![]), mark, anchor),
+            Event::MappingStart(anchor) => self.start(Value::Mapping(vec![]), mark, anchor),
+            Event::SequenceEnd | Event::MappingEnd => {
+                if let Some(frame) = self.stack.pop() {
+                    self.push(frame.node, frame.anchor);
+                }
+            }
+            Event::Alias(anchor) => {
+                // the parser rejects unknown anchors, so this is always found
+                if let Some(node) = self.anchors.get(&anchor).cloned() {
+                    self.push(Node { mark, ..node }, 0);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn scalar(node: &Node) -> &str {
+        match node.value {
+            Value::Scalar(ref s) => s,
+            _ => panic!("expected scalar, got {:?}", node),
+        }
+    }
+
+    #[test]
+    fn nested() {
+        let root = parse("- add: camera\n  from: [1, 2, 3]\n- define: x\n").unwrap();
+        let Value::Sequence(items) = root.value else {
+            panic!("expected sequence");
+        };
+        assert_eq!(items.len(), 2);
+        let Value::Mapping(ref entries) = items[0].value else {
+            panic!("expected mapping");
+        };
+        assert_eq!(scalar(&entries[0].0), "add");
+        assert_eq!(scalar(&entries[0].1), "camera");
+        assert_eq!(entries[0].1.mark, Mark { line: 1, column: 8 });
+        assert_eq!(scalar(&entries[1].0), "from");
+        let Value::Sequence(ref from) = entries[1].1.value else {
+            panic!("expected sequence");
+        };
+        assert_eq!(
+            from.iter().map(scalar).collect::<Vec<_>>(),
+            vec!["1", "2", "3"]
+        );
+        assert_eq!(
+            from[2].mark,
+            Mark {
+                line: 2,
+                column: 16
+            }
+        );
+    }
+
+    #[test]
+    fn aliases() {
+        let root = parse("a: &x [1, 2]\nb: *x\n").unwrap();
+        let Value::Mapping(entries) = root.value else {
+            panic!("expected mapping");
+        };
+        assert_eq!(entries[0].1.value, entries[1].1.value);
+        assert_eq!(entries[1].1.mark.line, 2);
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(parse("").unwrap().value, Value::Sequence(vec![]));
+    }
+
+    #[test]
+    fn syntax_error() {
+        let err = parse("a: [1, 2\n").unwrap_err();
+        assert_eq!(err.marker().line(), 2);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::{Color, Framebuffer};
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+/// How an image texture is sampled between the centers of its pixels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum TextureFilter {
+    /// Use the color of the nearest pixel
+    #[default]
+    Nearest,
+
+    /// Interpolate between the four nearest pixels
+    Bilinear,
+}
+
+/// How an image texture is sampled beyond its edges.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum TextureWrap {
+    /// Tile the image, so that its edges meet seamlessly
+    #[default]
+    Repeat,
+
+    /// Extend the edge pixels of the image
+    Clamp,
+}
+
+/// A two-dimensional texture backed by an image.  The image is shared, so copies of a texture
+/// are cheap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageTexture {
+    image: Arc<Framebuffer>,
+    filter: TextureFilter,
+    wrap: TextureWrap,
+}
+
+impl ImageTexture {
+    /// Create a texture from an image.  Panics if the image is empty.
+    pub fn new(image: Arc<Framebuffer>) -> Self {
+        assert!(!image.is_empty(), "texture image is empty");
+        Self {
+            image,
+            filter: TextureFilter::default(),
+            wrap: TextureWrap::default(),
+        }
+    }
+
+    /// Load a texture from an image file.  See `Framebuffer::load`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, image::ImageError> {
+        Self::from_image(Framebuffer::load(path)?)
+    }
+
+    /// Load a texture from an image file holding data rather than colors.  See
+    /// `Framebuffer::load_linear`.
+    pub fn load_linear<P: AsRef<Path>>(path: P) -> Result<Self, image::ImageError> {
+        Self::from_image(Framebuffer::load_linear(path)?)
+    }
+
+    fn from_image(image: Framebuffer) -> Result<Self, image::ImageError> {
+        if image.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "image is empty").into());
+        }
+        Ok(Self::new(Arc::new(image)))
+    }
+
+    /// Return an updated texture using the given filter.
+    pub fn with_filter(mut self, filter: TextureFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Return an updated texture using the given addressing beyond its edges.
+    pub fn with_wrap(mut self, wrap: TextureWrap) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    /// Calculate the color at the given (u, v) coordinates, where (0, 0) is the bottom left
+    /// corner of the image and (1, 1) is the top right.
+    pub fn color_at(&self, u: f64, v: f64) -> Color {
+        // coordinates in pixels, with pixel centers at half-integers
+        let x = u * self.image.width() as f64;
+        let y = (1.0 - v) * self.image.height() as f64;
+        match self.filter {
+            TextureFilter::Nearest => self.pixel(x.floor() as i64, y.floor() as i64),
+            TextureFilter::Bilinear => {
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (x0, y0) = (x.floor(), y.floor());
+                let (fx, fy) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as i64, y0 as i64);
+                let top = self.pixel(x0, y0) * (1.0 - fx) + self.pixel(x0 + 1, y0) * fx;
+                let bottom = self.pixel(x0, y0 + 1) * (1.0 - fx) + self.pixel(x0 + 1, y0 + 1) * fx;
+                top * (1.0 - fy) + bottom * fy
+            }
+        }
+    }
+
+    /// Get the pixel at the given coordinates, which may be beyond the edges of the image.
+    fn pixel(&self, x: i64, y: i64) -> Color {
+        let address = |c: i64, size: u32| match self.wrap {
+            TextureWrap::Repeat => c.rem_euclid(size as i64) as u32,
+            TextureWrap::Clamp => c.clamp(0, size as i64 - 1) as u32,
+        };
+        self.image.get(
+            address(x, self.image.width()),
+            address(y, self.image.height()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::*;
+
+    /// A 2x2 texture with black and white in the top row, and red and green in the bottom.
+    fn texture() -> ImageTexture {
+        let mut fb = Framebuffer::new(2, 2);
+        fb.set(1, 0, Color::white());
+        fb.set(0, 1, Color::new(1, 0, 0));
+        fb.set(1, 1, Color::new(0, 1, 0));
+        ImageTexture::new(Arc::new(fb))
+    }
+
+    #[test]
+    fn nearest() {
+        let t = texture();
+        for (u, v, expected) in [
+            (0.1, 0.9, Color::black()),
+            (0.9, 0.9, Color::white()),
+            (0.1, 0.1, Color::new(1, 0, 0)),
+            (0.6, 0.4, Color::new(0, 1, 0)),
+            (1.1, 0.9, Color::black()),
+            (-0.1, 0.9, Color::white()),
+        ] {
+            assert_relative_eq!(t.color_at(u, v), expected);
+        }
+    }
+
+    #[test]
+    fn nearest_clamp() {
+        let t = texture().with_wrap(TextureWrap::Clamp);
+        assert_relative_eq!(t.color_at(1.1, 0.9), Color::white());
+        assert_relative_eq!(t.color_at(-0.1, 0.9), Color::black());
+        assert_relative_eq!(t.color_at(0.1, -5.0), Color::new(1, 0, 0));
+    }
+
+    #[test]
+    fn bilinear() {
+        let t = texture().with_filter(TextureFilter::Bilinear);
+        // at pixel centers
+        assert_relative_eq!(t.color_at(0.25, 0.75), Color::black());
+        assert_relative_eq!(t.color_at(0.75, 0.25), Color::new(0, 1, 0));
+        // between pixels
+        assert_relative_eq!(t.color_at(0.5, 0.75), Color::new(0.5, 0.5, 0.5));
+        assert_relative_eq!(t.color_at(0.5, 0.5), Color::new(0.5, 0.5, 0.25));
+        // across the edge, wrapping to the other side
+        assert_relative_eq!(t.color_at(0.0, 0.75), Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn bilinear_clamp() {
+        let t = texture()
+            .with_filter(TextureFilter::Bilinear)
+            .with_wrap(TextureWrap::Clamp);
+        assert_relative_eq!(t.color_at(0.0, 0.75), Color::black());
+        assert_relative_eq!(t.color_at(1.0, 0.0), Color::new(0, 1, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "texture image is empty")]
+    fn empty_image() {
+        ImageTexture::new(Arc::new(Framebuffer::new(3, 0)));
+    }
+
+    #[test]
+    fn load_empty_image() {
+        let path =
+            std::env::temp_dir().join(format!("rtc-empty-texture-{}.ppm", std::process::id()));
+        std::fs::write(&path, "P3 0 0 255\n").unwrap();
+        let err = ImageTexture::load(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(err.to_string(), "image is empty");
+    }
+}
@@ -0,0 +1,213 @@
+use crate::{Color, Framebuffer};
+use image::{Rgb, RgbImage};
+use std::fmt;
+use std::str::FromStr;
+
+/// An operator mapping unbounded colors to the range [0, 1] for display.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ToneMapOperator {
+    /// Clip each component to 1, so anything brighter is white
+    #[default]
+    Clamp,
+
+    /// Compress each component `c` to `c / (1 + c)`, preserving detail in bright areas
+    Reinhard,
+
+    /// An approximation of the ACES filmic curve, with a gentle toe and shoulder
+    Aces,
+}
+
+impl ToneMapOperator {
+    fn apply(self, c: f64) -> f64 {
+        let c = c.max(0.0);
+        match self {
+            ToneMapOperator::Clamp => c.min(1.0),
+            ToneMapOperator::Reinhard => c / (1.0 + c),
+            ToneMapOperator::Aces => {
+                // Krzysztof Narkowicz's fit of the ACES reference rendering transform
+                let (a, b, c2, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+                ((c * (a * c + b)) / (c * (c2 * c + d) + e)).clamp(0.0, 1.0)
+            }
+        }
+    }
+}
+
+/// An error parsing a ToneMapOperator from a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownToneMapOperator(pub String);
+
+impl fmt::Display for UnknownToneMapOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown tone mapping operator {:?} (expected clamp, reinhard or aces)",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for UnknownToneMapOperator {}
+
+impl FromStr for ToneMapOperator {
+    type Err = UnknownToneMapOperator;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clamp" => Ok(ToneMapOperator::Clamp),
+            "reinhard" => Ok(ToneMapOperator::Reinhard),
+            "aces" => Ok(ToneMapOperator::Aces),
+            _ => Err(UnknownToneMapOperator(s.to_string())),
+        }
+    }
+}
+
+/// The conversion of rendered colors for display: an exposure adjustment, then a tone mapping
+/// operator, then optionally sRGB gamma encoding.
+///
+/// The default clamps colors without adjusting them, as the book does.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct ToneMap {
+    operator: ToneMapOperator,
+
+    /// Exposure adjustment, in stops
+    exposure: f64,
+
+    /// If true, encode the result with the sRGB transfer function
+    srgb: bool,
+}
+
+impl ToneMap {
+    pub fn new(operator: ToneMapOperator) -> Self {
+        Self {
+            operator,
+            ..Self::default()
+        }
+    }
+
+    /// Return an updated tone map which scales colors by `2^stops` before mapping them.
+    pub fn with_exposure(mut self, stops: f64) -> Self {
+        self.exposure = stops;
+        self
+    }
+
+    /// Return an updated tone map which does, or does not, apply sRGB gamma encoding.
+    pub fn with_srgb(mut self, srgb: bool) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
+    /// Map a single color to the range [0, 1].
+    pub fn map(&self, color: Color) -> Color {
+        let scale = 2f64.powf(self.exposure);
+        let map = |c: f64| {
+            let c = self.operator.apply(c * scale);
+            if self.srgb {
+                srgb_encode(c)
+            } else {
+                c
+            }
+        };
+        Color::new(map(color.red), map(color.green), map(color.blue))
+    }
+}
+
+/// Apply the sRGB transfer function to a linear value in [0, 1].
+fn srgb_encode(c: f64) -> f64 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Invert the sRGB transfer function, giving the linear value of an encoded value in [0, 1].
+pub(crate) fn srgb_decode(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl Framebuffer {
+    /// Convert this framebuffer to an 8-bit image, using the given tone map.
+    pub fn to_image(&self, tone_map: ToneMap) -> RgbImage {
+        RgbImage::from_fn(self.width(), self.height(), |x, y| {
+            Rgb::from(tone_map.map(self.get(x, y)))
+        })
+    }
+
+    /// Convert this framebuffer to a buffer of packed 8-bit RGB values, using the given tone
+    /// map.
+    pub fn to_u32_buffer(&self, tone_map: ToneMap, buf: &mut [u32]) {
+        debug_assert_eq!(buf.len(), self.pixels().len());
+        for (p, &c) in buf.iter_mut().zip(self.pixels()) {
+            *p = tone_map.map(c).into();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::*;
+
+    #[test]
+    fn clamp() {
+        let tm = ToneMap::default();
+        assert_relative_eq!(tm.map(Color::new(0.5, 2, -1)), Color::new(0.5, 1, 0));
+    }
+
+    #[test]
+    fn reinhard() {
+        let tm = ToneMap::new(ToneMapOperator::Reinhard);
+        assert_relative_eq!(tm.map(Color::new(0, 1, 3)), Color::new(0, 0.5, 0.75));
+    }
+
+    #[test]
+    fn aces() {
+        let tm = ToneMap::new(ToneMapOperator::Aces);
+        let c = tm.map(Color::new(0, 0.18, 100));
+        assert_relative_eq!(c.red, 0.0);
+        assert_relative_eq!(c.green, 0.2669, epsilon = 0.0001);
+        assert_relative_eq!(c.blue, 1.0);
+    }
+
+    #[test]
+    fn exposure() {
+        let tm = ToneMap::new(ToneMapOperator::Reinhard).with_exposure(1.0);
+        assert_relative_eq!(tm.map(Color::new(0.5, 0.5, 0.5)), Color::new(0.5, 0.5, 0.5));
+        let tm = ToneMap::default().with_exposure(-2.0);
+        assert_relative_eq!(tm.map(Color::new(2, 2, 2)), Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn srgb() {
+        let tm = ToneMap::default().with_srgb(true);
+        let c = tm.map(Color::new(0.001, 0.5, 1));
+        assert_relative_eq!(c.red, 0.01292);
+        assert_relative_eq!(c.green, 0.7354, epsilon = 0.0001);
+        assert_relative_eq!(c.blue, 1.0);
+    }
+
+    #[test]
+    fn operator_from_str() {
+        assert_eq!("aces".parse(), Ok(ToneMapOperator::Aces));
+        assert_eq!(
+            "filmic".parse::<ToneMapOperator>(),
+            Err(UnknownToneMapOperator("filmic".to_string()))
+        );
+    }
+
+    #[test]
+    fn to_image() {
+        let mut fb = Framebuffer::new(2, 1);
+        fb.set(1, 0, Color::new(0.5, 1, 3));
+        let img = fb.to_image(ToneMap::new(ToneMapOperator::Reinhard));
+        assert_eq!(img.get_pixel(0, 0), &Rgb([0, 0, 0]));
+        assert_eq!(img.get_pixel(1, 0), &Rgb([85, 128, 192]));
+        let mut buf = [0; 2];
+        fb.to_u32_buffer(ToneMap::default(), &mut buf);
+        assert_eq!(buf, [0, 0x80ffff]);
+    }
+}
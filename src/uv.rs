@@ -0,0 +1,300 @@
+use crate::{spaces, Color, ImageTexture, Point};
+use std::f64::consts::PI;
+
+/// A mapping from points in pattern space to two-dimensional (u, v) coordinates, each in the
+/// range [0, 1), used to wrap a UvPattern around an object.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum UvMapping {
+    /// Latitude and longitude on a sphere centered on the origin
+    Spherical,
+
+    /// The x and z coordinates, repeating every unit
+    Planar,
+
+    /// Angle around, and height along, the y axis, repeating every unit of height
+    Cylindrical,
+
+    /// Position on the face of the cube from -1 to 1 containing the point.  Every face shows
+    /// the same pattern; use `Pattern::cube_map` for a different pattern on each face.
+    Cubic,
+}
+
+impl UvMapping {
+    /// Map a point in pattern space to (u, v) coordinates.
+    pub fn map(self, p: Point<spaces::Pattern>) -> (f64, f64) {
+        match self {
+            UvMapping::Spherical => {
+                let theta = p.x.atan2(p.z);
+                let radius = (p.x * p.x + p.y * p.y + p.z * p.z).sqrt();
+                let phi = (p.y / radius).acos();
+                let raw_u = theta / (2.0 * PI);
+                (1.0 - (raw_u + 0.5), 1.0 - phi / PI)
+            }
+            UvMapping::Planar => (p.x.rem_euclid(1.0), p.z.rem_euclid(1.0)),
+            UvMapping::Cylindrical => {
+                let theta = p.x.atan2(p.z);
+                let raw_u = theta / (2.0 * PI);
+                (1.0 - (raw_u + 0.5), p.y.rem_euclid(1.0))
+            }
+            UvMapping::Cubic => CubeFace::from_point(p).map(p),
+        }
+    }
+}
+
+/// A face of the cube from -1 to 1 on each axis, in the order used by `Pattern::cube_map`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CubeFace {
+    Left,
+    Front,
+    Right,
+    Back,
+    Up,
+    Down,
+}
+
+impl CubeFace {
+    /// Get the face of the cube nearest to the given point.
+    pub fn from_point(p: Point<spaces::Pattern>) -> Self {
+        let coord = p.x.abs().max(p.y.abs()).max(p.z.abs());
+        if coord == p.x {
+            CubeFace::Right
+        } else if coord == -p.x {
+            CubeFace::Left
+        } else if coord == p.y {
+            CubeFace::Up
+        } else if coord == -p.y {
+            CubeFace::Down
+        } else if coord == p.z {
+            CubeFace::Front
+        } else {
+            CubeFace::Back
+        }
+    }
+
+    /// Map a point on this face to (u, v) coordinates, with v increasing upward as seen from
+    /// outside the cube (or, for the up and down faces, from the front).
+    pub fn map(self, p: Point<spaces::Pattern>) -> (f64, f64) {
+        let wrap = |c: f64| c.rem_euclid(2.0) / 2.0;
+        match self {
+            CubeFace::Front => (wrap(p.x + 1.0), wrap(p.y + 1.0)),
+            CubeFace::Back => (wrap(1.0 - p.x), wrap(p.y + 1.0)),
+            CubeFace::Left => (wrap(p.z + 1.0), wrap(p.y + 1.0)),
+            CubeFace::Right => (wrap(1.0 - p.z), wrap(p.y + 1.0)),
+            CubeFace::Up => (wrap(p.x + 1.0), wrap(1.0 - p.z)),
+            CubeFace::Down => (wrap(p.x + 1.0), wrap(p.z + 1.0)),
+        }
+    }
+}
+
+/// A pattern of colors in two dimensions, applied to an object with a UvMapping.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UvPattern {
+    /// A checkerboard of `width` by `height` squares
+    Checkers {
+        width: f64,
+        height: f64,
+        a: Color,
+        b: Color,
+    },
+
+    /// A color with a different color in each corner, useful to check the alignment of the
+    /// faces of a cube map
+    AlignCheck {
+        main: Color,
+        upper_left: Color,
+        upper_right: Color,
+        bottom_left: Color,
+        bottom_right: Color,
+    },
+
+    /// An image, stretched so that it covers the range of u and v once
+    Image(ImageTexture),
+}
+
+impl UvPattern {
+    /// Calculate the color at the given (u, v) coordinates.
+    pub fn color_at(&self, u: f64, v: f64) -> Color {
+        match *self {
+            UvPattern::Checkers {
+                width,
+                height,
+                a,
+                b,
+            } => {
+                let u2 = (u * width).floor();
+                let v2 = (v * height).floor();
+                if (u2 + v2).rem_euclid(2.0) < 1.0 {
+                    a
+                } else {
+                    b
+                }
+            }
+            UvPattern::AlignCheck {
+                main,
+                upper_left,
+                upper_right,
+                bottom_left,
+                bottom_right,
+            } => {
+                if v > 0.8 {
+                    if u < 0.2 {
+                        return upper_left;
+                    }
+                    if u > 0.8 {
+                        return upper_right;
+                    }
+                } else if v < 0.2 {
+                    if u < 0.2 {
+                        return bottom_left;
+                    }
+                    if u > 0.8 {
+                        return bottom_right;
+                    }
+                }
+                main
+            }
+            UvPattern::Image(ref texture) => texture.color_at(u, v),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::*;
+
+    fn checkers() -> UvPattern {
+        UvPattern::Checkers {
+            width: 2.0,
+            height: 2.0,
+            a: Color::black(),
+            b: Color::white(),
+        }
+    }
+
+    #[test]
+    fn uv_checkers() {
+        let p = checkers();
+        for (u, v, expected) in [
+            (0.0, 0.0, Color::black()),
+            (0.5, 0.0, Color::white()),
+            (0.0, 0.5, Color::white()),
+            (0.5, 0.5, Color::black()),
+            (1.0, 1.0, Color::black()),
+        ] {
+            assert_eq!(p.color_at(u, v), expected);
+        }
+    }
+
+    #[test]
+    fn spherical() {
+        let s2 = 2f64.sqrt() / 2.0;
+        for (p, u, v) in [
+            (Point::new(0, 0, -1), 0.0, 0.5),
+            (Point::new(1, 0, 0), 0.25, 0.5),
+            (Point::new(0, 0, 1), 0.5, 0.5),
+            (Point::new(-1, 0, 0), 0.75, 0.5),
+            (Point::new(0, 1, 0), 0.5, 1.0),
+            (Point::new(0, -1, 0), 0.5, 0.0),
+            (Point::new(s2, s2, 0), 0.25, 0.75),
+        ] {
+            let (mu, mv) = UvMapping::Spherical.map(p);
+            assert_relative_eq!(mu, u);
+            assert_relative_eq!(mv, v);
+        }
+    }
+
+    #[test]
+    fn planar() {
+        for (p, u, v) in [
+            (Point::new(0.25, 0, 0.5), 0.25, 0.5),
+            (Point::new(0.25, 0, -0.25), 0.25, 0.75),
+            (Point::new(0.25, 0.5, -0.25), 0.25, 0.75),
+            (Point::new(1.25, 0, 0.5), 0.25, 0.5),
+            (Point::new(0.25, 0, -1.75), 0.25, 0.25),
+            (Point::new(1, 0, -1), 0.0, 0.0),
+            (Point::new(0, 0, 0), 0.0, 0.0),
+        ] {
+            let (mu, mv) = UvMapping::Planar.map(p);
+            assert_relative_eq!(mu, u);
+            assert_relative_eq!(mv, v);
+        }
+    }
+
+    #[test]
+    fn cylindrical() {
+        let s2 = 2f64.sqrt() / 2.0;
+        for (p, u, v) in [
+            (Point::new(0, 0, -1), 0.0, 0.0),
+            (Point::new(0, 0.5, -1), 0.0, 0.5),
+            (Point::new(0, 1, -1), 0.0, 0.0),
+            (Point::new(s2, 0.5, -s2), 0.125, 0.5),
+            (Point::new(1, 0.5, 0), 0.25, 0.5),
+            (Point::new(s2, 0.5, s2), 0.375, 0.5),
+            (Point::new(0, -0.25, 1), 0.5, 0.75),
+            (Point::new(-s2, 0.5, s2), 0.625, 0.5),
+            (Point::new(-1, 1.25, 0), 0.75, 0.25),
+            (Point::new(-s2, 0.5, -s2), 0.875, 0.5),
+        ] {
+            let (mu, mv) = UvMapping::Cylindrical.map(p);
+            assert_relative_eq!(mu, u);
+            assert_relative_eq!(mv, v);
+        }
+    }
+
+    #[test]
+    fn align_check() {
+        let p = UvPattern::AlignCheck {
+            main: Color::white(),
+            upper_left: Color::new(1, 0, 0),
+            upper_right: Color::new(1, 1, 0),
+            bottom_left: Color::new(0, 1, 0),
+            bottom_right: Color::new(0, 1, 1),
+        };
+        for (u, v, expected) in [
+            (0.5, 0.5, Color::white()),
+            (0.1, 0.9, Color::new(1, 0, 0)),
+            (0.9, 0.9, Color::new(1, 1, 0)),
+            (0.1, 0.1, Color::new(0, 1, 0)),
+            (0.9, 0.1, Color::new(0, 1, 1)),
+        ] {
+            assert_eq!(p.color_at(u, v), expected);
+        }
+    }
+
+    #[test]
+    fn cube_face() {
+        for (p, face) in [
+            (Point::new(-1, 0.5, -0.25), CubeFace::Left),
+            (Point::new(1.1, -0.75, 0.8), CubeFace::Right),
+            (Point::new(0.1, 0.6, 0.9), CubeFace::Front),
+            (Point::new(-0.7, 0, -2), CubeFace::Back),
+            (Point::new(0.5, 1, 0.9), CubeFace::Up),
+            (Point::new(-0.2, -1.3, 1.1), CubeFace::Down),
+        ] {
+            assert_eq!(CubeFace::from_point(p), face);
+        }
+    }
+
+    #[test]
+    fn cube_face_uv() {
+        for (face, p, u, v) in [
+            (CubeFace::Front, Point::new(-0.5, 0.5, 1), 0.25, 0.75),
+            (CubeFace::Front, Point::new(0.5, -0.5, 1), 0.75, 0.25),
+            (CubeFace::Back, Point::new(0.5, 0.5, -1), 0.25, 0.75),
+            (CubeFace::Back, Point::new(-0.5, -0.5, -1), 0.75, 0.25),
+            (CubeFace::Left, Point::new(-1, 0.5, -0.5), 0.25, 0.75),
+            (CubeFace::Left, Point::new(-1, -0.5, 0.5), 0.75, 0.25),
+            (CubeFace::Right, Point::new(1, 0.5, 0.5), 0.25, 0.75),
+            (CubeFace::Right, Point::new(1, -0.5, -0.5), 0.75, 0.25),
+            (CubeFace::Up, Point::new(-0.5, 1, -0.5), 0.25, 0.75),
+            (CubeFace::Up, Point::new(0.5, 1, 0.5), 0.75, 0.25),
+            (CubeFace::Down, Point::new(-0.5, -1, 0.5), 0.25, 0.75),
+            (CubeFace::Down, Point::new(0.5, -1, -0.5), 0.75, 0.25),
+        ] {
+            let (mu, mv) = face.map(p);
+            assert_relative_eq!(mu, u);
+            assert_relative_eq!(mv, v);
+        }
+    }
+}
@@ -1,12 +1,20 @@
-use crate::csg::Object;
-use crate::{spaces, Color, Intersections, Point, Ray, RayColor, Vector};
+use crate::bvh::Bvh;
+use crate::csg::{Node, Object};
+use crate::{
+    spaces, Background, Color, Intersections, Light, LightAt, Mat, Point, Ray, RayColor, Vector,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
 
 /// The minimum total_contribution for which color_at_inner will make a calculation
 const MIN_CONTRIBUTION: f64 = 0.001;
 
+/// The number of sets of ray counters in a world, shared among threads
+const RAY_COUNTERS: usize = 64;
+
 /// An index into the objects in a world.
 #[derive(Debug, Clone, Copy, PartialEq)]
-pub struct ObjectIndex(usize);
+pub struct ObjectIndex(pub(crate) usize);
 
 impl ObjectIndex {
     #[cfg(test)]
@@ -15,75 +23,145 @@ impl ObjectIndex {
     }
 }
 
-/// A representation of a light in the world.
-#[derive(Debug, Copy, Clone, PartialEq, Default)]
-pub struct Light {
-    pub position: Point<spaces::World>,
-    pub intensity: Color,
+/// The range of primitive objects added to a world from a group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GroupIndex {
+    start: usize,
+    end: usize,
 }
 
-impl Light {
-    pub fn new_point(position: Point<spaces::World>, intensity: Color) -> Self {
-        Self {
-            position,
-            intensity,
-        }
+impl GroupIndex {
+    /// Get the ObjectIndex of the first primitive in the group.
+    pub fn first(&self) -> ObjectIndex {
+        ObjectIndex(self.start)
     }
-}
-
-/// A representation of light's effect at a particular point
-#[derive(Debug, Copy, Clone, PartialEq, Default)]
-pub(crate) struct LightAt {
-    /// The intensity of the light
-    pub(crate) intensity: Color,
-
-    /// Direction from the given point to the light
-    pub(crate) direction: Vector<spaces::World>,
 
-    /// Whether the point is in shadow
-    pub(crate) in_shadow: bool,
+    /// Get the ObjectIndex of each primitive in the group.
+    pub fn iter(&self) -> impl Iterator<Item = ObjectIndex> {
+        (self.start..self.end).map(ObjectIndex)
+    }
 }
 
+/// An index into the lights in a world.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LightIndex(pub(crate) usize);
+
 /// World describes an entire world to be rendered.
 #[derive(Debug)]
 pub struct World {
-    pub(crate) light: Light,
-    objects: Vec<Object>,
+    /// The lights illuminating this world, indexed by LightIndex.
+    pub(crate) lights: Vec<Light>,
+
+    /// The color seen by rays which hit nothing.
+    pub(crate) background: Background,
+
+    /// The primitive objects in this world, indexed by ObjectIndex.
+    pub(crate) objects: Vec<Object>,
+
+    /// The units in which `objects` are intersected.
+    nodes: Vec<Node>,
+
+    /// Bounding volume hierarchy over `nodes`, built on demand and discarded whenever
+    /// an object is added.
+    bvh: OnceLock<Bvh>,
+
+    /// Counts of the rays cast, kept separately for each rendering thread so that threads do
+    /// not contend for them, and summed by `ray_stats`.
+    ray_counters: Vec<RayCounters>,
+}
+
+/// The counts of rays cast by one thread, aligned to avoid sharing a cache line with another
+/// thread's counts.
+#[derive(Debug, Default)]
+#[repr(align(128))]
+struct RayCounters {
+    /// Number of rays whose color has been calculated
+    rays: AtomicU64,
+
+    /// Number of rays cast toward lights
+    shadow_rays: AtomicU64,
+}
+
+/// Counts of the rays cast in a world.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct RayStats {
+    /// Rays whose color was calculated, including reflected and refracted rays
+    pub rays: u64,
+
+    /// Rays cast to determine whether a point is in shadow
+    pub shadow_rays: u64,
 }
 
 impl Default for World {
     fn default() -> Self {
-        Self {
-            light: Light::new_point(Point::new(-10, 10, -10), Color::white()),
-            objects: vec![],
-        }
+        Self::new(Light::new_point(Point::new(-10, 10, -10), Color::white()))
     }
 }
 
 impl World {
     pub fn new(light: Light) -> Self {
         Self {
-            light,
+            lights: vec![light],
+            background: Background::default(),
             objects: vec![],
+            nodes: vec![],
+            bvh: OnceLock::new(),
+            ray_counters: (0..RAY_COUNTERS).map(|_| RayCounters::default()).collect(),
         }
     }
 
     /// Add a new object to this world, returning its ObjectIndex.
+    ///
+    /// Groups and CSG objects are flattened into the primitive objects they contain, and the
+    /// returned ObjectIndex is that of the first such primitive.  Panics if there are none, as
+    /// for an empty group; use `add_group` for groups which may be empty.
     pub fn add_object(&mut self, obj: Object) -> ObjectIndex {
-        let idx = ObjectIndex(self.objects.len());
-        self.objects.push(obj);
-        idx
+        self.add_group(obj)
+            .expect("object contains no primitives")
+            .first()
+    }
+
+    /// Add a group, or any other object, to this world, returning the range of primitive
+    /// objects it was flattened into, or None if it contains no primitives.
+    pub fn add_group(&mut self, group: Object) -> Option<GroupIndex> {
+        let start = self.objects.len();
+        group.flatten_into(Mat::identity(), None, &mut self.objects, &mut self.nodes);
+        self.bvh.take();
+        let end = self.objects.len();
+        (start < end).then_some(GroupIndex { start, end })
+    }
+
+    /// Add a new light to this world, returning its LightIndex.
+    pub fn add_light(&mut self, light: Light) -> LightIndex {
+        self.lights.push(light);
+        LightIndex(self.lights.len() - 1)
     }
 
+    /// Replace all of the lights in this world with the given light.
     pub fn set_light(&mut self, light: Light) {
-        self.light = light;
+        self.lights = vec![light];
+    }
+
+    /// Set the color seen by rays which hit nothing, including reflected and refracted rays.
+    pub fn set_background(&mut self, background: Background) {
+        self.background = background;
+    }
+
+    /// Get the number of rays cast in this world so far.
+    pub fn ray_stats(&self) -> RayStats {
+        self.ray_counters
+            .iter()
+            .fold(RayStats::default(), |acc, c| RayStats {
+                rays: acc.rays + c.rays.load(Ordering::Relaxed),
+                shadow_rays: acc.shadow_rays + c.shadow_rays.load(Ordering::Relaxed),
+            })
     }
 
     /// Create the "default_world" from the tests.
     #[cfg(test)]
     pub(crate) fn test_world() -> Self {
         use crate::csg::Sphere;
-        use crate::{Mat, Material};
+        use crate::Material;
         let mut w = World::default();
         w.add_object(
             Object::new(Sphere).with_material(
@@ -101,36 +179,132 @@ impl World {
         w
     }
 
+    /// Get the BVH for this world, building it if necessary.
+    fn bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| {
+            let bounds: Vec<_> = self.nodes.iter().map(|n| n.bounds(&self.objects)).collect();
+            Bvh::new(&bounds)
+        })
+    }
+
     /// Intersect the given ray with all objects in the world.
+    ///
+    /// Objects which lie entirely behind the ray's origin may be omitted.
     pub(crate) fn intersect(&self, ray: &Ray<spaces::World>, inters: &mut Intersections) {
-        for (i, o) in self.objects.iter().enumerate() {
-            o.intersect(ObjectIndex(i), ray, inters);
-        }
+        self.bvh().intersect(ray, |i| {
+            self.nodes[i].intersect(&self.objects, ray, inters);
+        });
+    }
+
+    /// Get the ray counters for the current thread.  Threads outside the rendering thread
+    /// pool share the first set.
+    fn ray_counters(&self) -> &RayCounters {
+        let thread = rayon::current_thread_index().map_or(0, |i| i + 1);
+        &self.ray_counters[thread % RAY_COUNTERS]
+    }
+
+    /// Intersect the given ray with all objects in the world, counting it in the ray
+    /// statistics.
+    pub(crate) fn cast(&self, ray: &Ray<spaces::World>) -> Intersections {
+        self.ray_counters().rays.fetch_add(1, Ordering::Relaxed);
+        let mut inters = Intersections::default();
+        self.intersect(ray, &mut inters);
+        inters
+    }
+
+    /// Get the emissive objects in this world which can be sampled as lights: those with a
+    /// finite surface area which are not part of a CSG combination, since only some of their
+    /// surface would be visible.
+    pub(crate) fn emitters(&self) -> Vec<ObjectIndex> {
+        self.nodes
+            .iter()
+            .filter_map(|node| match *node {
+                Node::Primitive(idx) => Some(idx),
+                Node::Csg { .. } => None,
+            })
+            .filter(|&idx| {
+                let obj = &self[idx];
+                obj.material().emission != Color::black() && obj.surface_area().is_some()
+            })
+            .collect()
     }
 
-    fn point_is_shadowed(&self, point: Point<spaces::World>) -> bool {
-        let to_light = self.light.position - point;
-        let to_light_norm = to_light.normalize();
+    /// Determine the color of the light reaching a point from the given distance, in the given
+    /// (normalized) direction toward a light.
+    ///
+    /// Opaque objects block the light entirely, while transparent objects filter it through
+    /// their color once for each object the light passes through, however many of its
+    /// surfaces it crosses.
+    pub(crate) fn shadow_color(
+        &self,
+        point: Point<spaces::World>,
+        to_light: Vector<spaces::World>,
+        distance: f64,
+    ) -> Color {
         // move 0.01 along the ray to escape the object on which point
         // is situated
-        let to_light_ray = Ray::new(point + to_light_norm * 0.01, to_light_norm);
+        let to_light_ray = Ray::new(point + to_light * 0.01, to_light);
+        self.ray_counters()
+            .shadow_rays
+            .fetch_add(1, Ordering::Relaxed);
 
         let mut inters = Intersections::default();
         self.intersect(&to_light_ray, &mut inters);
-        if let (_, Some(t), _) = inters.hit() {
-            t < to_light.magnitude()
-        } else {
-            false
+        let mut color = Color::white();
+        // the objects containing the ray, and whether each was entered toward the light
+        let mut containers: Vec<(ObjectIndex, bool)> = Vec::new();
+        for hit in inters.iter() {
+            if hit.t >= distance || color == Color::black() {
+                break;
+            }
+            let ahead = hit.t >= 0.0;
+            let filter = match containers.iter().position(|&(c, _)| c == hit.container) {
+                // leaving an object filters the light only if the point is inside it, as
+                // otherwise the light was filtered on entry
+                Some(i) => ahead && !containers.remove(i).1,
+                None => {
+                    containers.push((hit.container, ahead));
+                    ahead
+                }
+            };
+            if filter {
+                let obj = &self.objects[hit.object_index.0];
+                color = color * obj.transmission(to_light_ray.position(hit.t));
+            }
         }
+        color
     }
 
-    /// Calculate the effect of the world's light at the given point.
-    pub(crate) fn light_at(&self, point: Point<spaces::World>) -> LightAt {
-        LightAt {
-            intensity: self.light.intensity,
-            direction: (self.light.position - point).normalize(),
-            in_shadow: self.point_is_shadowed(point),
+    /// Calculate the color of the given light which reaches the given point, as a fraction of
+    /// each component of its intensity.
+    fn light_visibility(&self, light: &Light, point: Point<spaces::World>) -> Color {
+        let samples = light.samples();
+        let mut color = Color::black();
+        for i in 0..samples {
+            let (to_light, distance) = light.shadow_ray(i, point);
+            color += self.shadow_color(point, to_light, distance);
         }
+        color / samples as f64
+    }
+
+    /// Calculate the effect of each of the world's lights at the given point.
+    pub(crate) fn lights_at(
+        &self,
+        point: Point<spaces::World>,
+    ) -> impl Iterator<Item = LightAt> + '_ {
+        self.lights.iter().map(move |light| {
+            let factor = light.intensity_factor(point);
+            LightAt {
+                intensity: light.intensity * factor,
+                direction: light.direction_from(point),
+                // no need to test shadows where there is no light
+                visibility: if factor > 0.0 {
+                    self.light_visibility(light, point)
+                } else {
+                    Color::black()
+                },
+            }
+        })
     }
 
     /// Determine the color received by an eye at the origin of the given ray, with
@@ -152,25 +326,23 @@ impl World {
             return Color::black();
         }
 
-        let mut inters = Intersections::default();
-        self.intersect(ray, &mut inters);
+        let mut inters = self.cast(ray);
         if debug {
             dbg!(&inters);
         }
-        if let (from_obj_idx, Some(t), to_obj_idx) = inters.hit() {
+        if let (from_obj_idx, Some(hit), to_obj_idx) = inters.hit() {
             if debug {
-                dbg!((from_obj_idx, Some(t), to_obj_idx));
+                dbg!((from_obj_idx, hit, to_obj_idx));
             }
             let from_obj = from_obj_idx.map(|i| &self.objects[i.0]);
             let to_obj = to_obj_idx.map(|i| &self.objects[i.0]);
-            // one of from_obj or to_obj must exist, since we got a `t` value
-            let hit_obj = to_obj.or(from_obj).expect("should have had an object");
-            hit_obj.color_at(self, from_obj, t, ray, total_contribution, debug)
+            let hit_obj = &self.objects[hit.object_index.0];
+            hit_obj.color_at(self, from_obj, to_obj, &hit, ray, total_contribution, debug)
         } else {
             if debug {
                 println!("no hits");
             }
-            Color::black()
+            self.background.color_at(ray.direction)
         }
     }
 }
@@ -192,12 +364,22 @@ impl std::ops::Index<ObjectIndex> for World {
     }
 }
 
+/// Index the world by LightIndex to get a light reference.
+impl std::ops::Index<LightIndex> for World {
+    type Output = Light;
+
+    fn index(&self, idx: LightIndex) -> &Light {
+        &self.lights[idx.0]
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::csg::*;
     use crate::*;
     use approx::*;
+    use std::f64::consts::PI;
 
     #[test]
     fn intersect_world_with_ray() {
@@ -213,6 +395,44 @@ mod test {
         assert!(it.next().is_none());
     }
 
+    #[test]
+    fn intersect_many_objects() {
+        // a row of 100 small spheres along the x axis, plus a floor
+        let mut w = World::default();
+        let floor =
+            w.add_object(Object::new(Plane).with_transform(Mat::identity().translate(0, -1, 0)));
+        let mut spheres = vec![];
+        for x in 0..100 {
+            spheres.push(
+                w.add_object(
+                    Object::new(Sphere)
+                        .with_transform(Mat::identity().scale(0.25, 0.25, 0.25).translate(x, 0, 0)),
+                ),
+            );
+        }
+
+        // a ray down onto one sphere hits it, then the floor
+        let r = Ray::new(Point::new(42, 5, 0), Vector::new(0, -1, 0));
+        let mut inters = Intersections::default();
+        w.intersect(&r, &mut inters);
+        let hits: Vec<_> = inters.iter().map(|i| (i.t, i.object_index)).collect();
+        assert_eq!(hits.len(), 3);
+        assert_relative_eq!(hits[0].0, 4.75);
+        assert_eq!(hits[0].1, spheres[42]);
+        assert_relative_eq!(hits[1].0, 5.25);
+        assert_eq!(hits[1].1, spheres[42]);
+        assert_relative_eq!(hits[2].0, 6.0);
+        assert_eq!(hits[2].1, floor);
+
+        // adding another object rebuilds the BVH
+        let extra =
+            w.add_object(Object::new(Sphere).with_transform(Mat::identity().translate(42, 3, 0)));
+        let mut inters = Intersections::default();
+        w.intersect(&r, &mut inters);
+        assert_eq!(inters.len(), 5);
+        assert_eq!(inters.iter().next().unwrap().object_index, extra);
+    }
+
     #[test]
     fn color_at_miss() {
         let w = World::test_world();
@@ -220,6 +440,55 @@ mod test {
         assert_relative_eq!(w.color_at(&r, 1.0, true), Color::black());
     }
 
+    #[test]
+    fn color_at_miss_background() {
+        let mut w = World::test_world();
+        w.set_background(Background::Solid(Color::new(0.2, 0.4, 0.6)));
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 1, 0));
+        assert_relative_eq!(w.color_at(&r, 1.0, true), Color::new(0.2, 0.4, 0.6));
+    }
+
+    #[test]
+    fn background_reflected() {
+        let mut w = World::new(Light::new_point(Point::new(0, 10, -10), Color::black()));
+        w.set_background(Background::Gradient {
+            horizon: Color::black(),
+            zenith: Color::new(0, 0, 1),
+        });
+        w.add_object(
+            Object::new(Plane).with_material(
+                Material::default()
+                    .with_color(Color::black())
+                    .with_reflectivity(0.5),
+            ),
+        );
+        // reflected straight up from the floor
+        let r = Ray::new(Point::new(0, 1, 0), Vector::new(0, -1, 0));
+        assert_relative_eq!(w.color_at(&r, 1.0, true), Color::new(0, 0, 0.5));
+    }
+
+    #[test]
+    fn bumped_surface() {
+        let world = |material: Material| {
+            let mut w = World::new(Light::new_point(Point::new(0, 10, 0), Color::white()));
+            w.add_object(Object::new(Plane).with_material(material));
+            w
+        };
+        let flat = world(Material::default());
+        let waves = world(Material::default().with_normal_modifier(NormalModifier::waves(0.1)));
+        let down = Vector::new(0, -1, 0);
+        // on a slope, the surface faces away from the light
+        let r = Ray::new(Point::new(0, 1, 0), down);
+        assert!(waves.color_at(&r, 1.0, true).red < flat.color_at(&r, 1.0, true).red);
+        // at a crest, the surface is level
+        let r = Ray::new(Point::new(0.25, 1, 0), down);
+        assert_relative_eq!(
+            waves.color_at(&r, 1.0, true),
+            flat.color_at(&r, 1.0, true),
+            epsilon = 1e-6
+        );
+    }
+
     #[test]
     fn color_at_hit() {
         let w = World::test_world();
@@ -234,6 +503,40 @@ mod test {
         );
     }
 
+    #[test]
+    fn ray_stats() {
+        let w = World::test_world();
+        let r = Ray::new(Point::new(0, 0, -5), Vector::new(0, 0, 1));
+        w.color_at(&r, 1.0, false);
+        let r = Ray::new(Point::new(0, 5, -5), Vector::new(0, 0, 1));
+        w.color_at(&r, 1.0, false);
+        // two rays, of which one hit an object and checked for a shadow
+        assert_eq!(
+            w.ray_stats(),
+            RayStats {
+                rays: 2,
+                shadow_rays: 1
+            }
+        );
+    }
+
+    #[test]
+    fn ray_stats_across_threads() {
+        let w = World::test_world();
+        let c = Camera::new(
+            20,
+            10,
+            PI / 2.0,
+            Point::new(0, 0, -5),
+            Point::new(0, 0, 0),
+            Vector::new(0, 1, 0),
+            1,
+        );
+        c.render(&w);
+        // one ray per pixel, with no reflection or refraction
+        assert_eq!(w.ray_stats().rays, 200);
+    }
+
     #[test]
     fn color_at_behind_ray() {
         let mut w = World::default();
@@ -255,31 +558,238 @@ mod test {
         assert_relative_eq!(w.color_at(&r, 1.0, true), Color::white());
     }
 
+    #[test]
+    fn add_and_set_lights() {
+        let mut w = World::default();
+        let l1 = Light::new_point(Point::new(0, 10, 0), Color::new(0.5, 0.5, 0.5));
+        let l2 = Light::new_point(Point::new(0, -10, 0), Color::white());
+        assert_eq!(w.add_light(l1), LightIndex(1));
+        assert_eq!(w.add_light(l2), LightIndex(2));
+        assert_eq!(w[LightIndex(1)], l1);
+        assert_eq!(w.lights.len(), 3);
+        w.set_light(l2);
+        assert_eq!(w.lights, vec![l2]);
+    }
+
+    #[test]
+    fn shadow_tested_per_light() {
+        let mut w = World::test_world();
+        w.add_light(Light::new_point(Point::new(10, -10, 10), Color::white()));
+        // the spheres lie between the first light and this point, but not the second
+        let p = Point::new(10, -10, 9);
+        let visibility: Vec<Color> = w.lights_at(p).map(|l| l.visibility).collect();
+        assert_eq!(visibility, vec![Color::black(), Color::white()]);
+    }
+
+    #[test]
+    fn point_light_visibility() {
+        let w = World::test_world();
+        let light = w.lights[0];
+        for (point, expected) in [
+            (Point::new(0, 1.0001, 0), 1.0),
+            (Point::new(-1.0001, 0, 0), 1.0),
+            (Point::new(0, 0, -1.0001), 1.0),
+            (Point::new(0, 0, 1.0001), 0.0),
+            (Point::new(1.0001, 0, 0), 0.0),
+            (Point::new(0, -1.0001, 0), 0.0),
+            (Point::new(0, 0, 0), 0.0),
+        ] {
+            assert_relative_eq!(w.light_visibility(&light, point), Color::white() * expected);
+        }
+    }
+
+    #[test]
+    fn area_light_visibility() {
+        let w = World::test_world();
+        let light = Light::new_area(
+            Point::new(-0.5, -0.5, -5),
+            Vector::new(1, 0, 0),
+            2,
+            Vector::new(0, 1, 0),
+            2,
+            Color::white(),
+        );
+        for (point, expected) in [
+            (Point::new(0, 0, 2), 0.0),
+            (Point::new(1, -1, 2), 0.25),
+            (Point::new(1.5, 0, 2), 0.5),
+            (Point::new(1.25, 1.25, 3), 0.75),
+            (Point::new(0, 0, -2), 1.0),
+        ] {
+            assert_relative_eq!(w.light_visibility(&light, point), Color::white() * expected);
+        }
+    }
+
+    #[test]
+    fn spot_light() {
+        let mut w = World::test_world();
+        w.set_light(Light::new_spot(
+            Point::new(0, 0, -10),
+            Vector::new(0, 0, 1),
+            PI / 6.0,
+            PI / 3.0,
+            Color::white(),
+        ));
+        // on the axis, the light is at full intensity
+        let on_axis = w.lights_at(Point::new(0, 0, -1)).next().unwrap();
+        assert_relative_eq!(on_axis.intensity, Color::white());
+        assert_relative_eq!(on_axis.visibility, Color::white());
+        // outside the cone, there is no light
+        let outside = w.lights_at(Point::new(0, 20, 0)).next().unwrap();
+        assert_relative_eq!(outside.intensity, Color::black());
+        // behind the spheres, the point is shadowed
+        let behind = w.lights_at(Point::new(0, 0, 2)).next().unwrap();
+        assert_relative_eq!(behind.intensity, Color::white());
+        assert_relative_eq!(behind.visibility, Color::black());
+    }
+
+    #[test]
+    fn directional_light() {
+        let mut w = World::test_world();
+        w.set_light(Light::new_directional(
+            Vector::new(0, -1, 0),
+            Color::white(),
+        ));
+        // directly below the spheres, however far away, the point is shadowed
+        let below = w.lights_at(Point::new(0.5, -1000, 0.5)).next().unwrap();
+        assert_relative_eq!(below.direction, Vector::new(0, 1, 0));
+        assert_relative_eq!(below.visibility, Color::black());
+        let beside = w.lights_at(Point::new(1.5, -1000, 0)).next().unwrap();
+        assert_relative_eq!(beside.visibility, Color::white());
+    }
+
+    /// Determine whether the given point is entirely in shadow from the world's first light.
+    fn shadowed(w: &World, p: Point<spaces::World>) -> bool {
+        w.light_visibility(&w.lights[0], p) == Color::black()
+    }
+
+    #[test]
+    fn colored_shadow() {
+        let mut w = World::new(Light::new_point(Point::new(0, 10, 0), Color::white()));
+        w.add_object(
+            Object::new(Sphere).with_material(
+                Material::default()
+                    .with_color(Color::new(1, 0.5, 0))
+                    .with_transparency(0.8, 1.5),
+            ),
+        );
+        // light passes through two surfaces of the sphere, but is filtered once
+        assert_relative_eq!(
+            w.light_visibility(&w.lights[0], Point::new(0, -2, 0)),
+            Color::new(0.8, 0.4, 0)
+        );
+        // from inside the sphere, light is filtered as it leaves
+        assert_relative_eq!(
+            w.light_visibility(&w.lights[0], Point::new(0, 0, 0)),
+            Color::new(0.8, 0.4, 0)
+        );
+        assert_relative_eq!(
+            w.light_visibility(&w.lights[0], Point::new(2, -2, 0)),
+            Color::white()
+        );
+    }
+
+    #[test]
+    fn shadow_filtered_by_each_object() {
+        let mut w = World::new(Light::new_point(Point::new(0, 10, 0), Color::white()));
+        for y in [0, 3] {
+            w.add_object(
+                Object::new(Sphere)
+                    .with_material(Material::default().with_transparency(0.5, 1.5))
+                    .with_transform(Mat::identity().translate(0, y, 0)),
+            );
+        }
+        assert_relative_eq!(
+            w.light_visibility(&w.lights[0], Point::new(0, -2, 0)),
+            Color::new(0.25, 0.25, 0.25)
+        );
+    }
+
+    #[test]
+    fn opaque_object_blocks_transparent_shadow() {
+        let mut w = World::new(Light::new_point(Point::new(0, 10, 0), Color::white()));
+        w.add_object(
+            Object::new(Sphere)
+                .with_material(Material::default().with_transparency(0.9, 1.5))
+                .with_transform(Mat::identity().translate(0, 3, 0)),
+        );
+        w.add_object(Object::new(Sphere));
+        assert_relative_eq!(
+            w.light_visibility(&w.lights[0], Point::new(0, -2, 0)),
+            Color::black()
+        );
+    }
+
     #[test]
     fn no_shadow_when_nothing_collinear() {
         let w = World::test_world();
         let p = Point::new(0, 0, -5);
-        assert!(!w.point_is_shadowed(p));
+        assert!(!shadowed(&w, p));
     }
 
     #[test]
     fn shadow_when_obj_intervenes() {
         let w = World::test_world();
         let p = Point::new(10, -10, 10);
-        assert!(w.point_is_shadowed(p));
+        assert!(shadowed(&w, p));
     }
 
     #[test]
     fn no_shadow_when_obj_behind_light() {
         let w = World::test_world();
         let p = Point::new(-20, 20, -20);
-        assert!(!w.point_is_shadowed(p));
+        assert!(!shadowed(&w, p));
     }
 
     #[test]
     fn no_shadow_when_obj_behind_point() {
         let w = World::test_world();
         let p = Point::new(-2, 2, -2);
-        assert!(!w.point_is_shadowed(p));
+        assert!(!shadowed(&w, p));
+    }
+
+    #[test]
+    fn emitters() {
+        let mut w = World::test_world();
+        let glow = Material::default().with_emission(Color::white());
+        let lamp = w.add_object(Object::new(Sphere).with_material(glow.clone()));
+        // unbounded, and only partly visible
+        w.add_object(Object::new(Plane).with_material(glow.clone()));
+        w.add_object(
+            Object::csg(Csg::union(Object::new(Sphere), Object::new(Cube))).with_material(glow),
+        );
+        assert_eq!(w.emitters(), vec![lamp]);
+    }
+
+    #[test]
+    fn add_group() {
+        let mut w = World::default();
+        assert_eq!(
+            w.add_group(Object::group(
+                Group::new().with_child(Object::group(Group::new()))
+            )),
+            None
+        );
+        let s = w.add_object(Object::new(Sphere));
+        assert_eq!(s, ObjectIndex::test_value(0));
+        let g = w
+            .add_group(Object::group(
+                Group::new()
+                    .with_child(Object::new(Sphere))
+                    .with_child(Object::new(Cube)),
+            ))
+            .unwrap();
+        assert_eq!(g.first(), ObjectIndex::test_value(1));
+        assert_eq!(
+            g.iter().collect::<Vec<_>>(),
+            vec![ObjectIndex::test_value(1), ObjectIndex::test_value(2)]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn add_empty_object() {
+        let mut w = World::default();
+        w.add_object(Object::group(Group::new()));
     }
 }